@@ -0,0 +1,19 @@
+#!/bin/bash
+# 编译 focus-guard-notify 并放到 src-tauri/binaries，作为 bundle.externalBin 打进安装包
+# 文件名需要带上目标平台的 triple，Tauri 打包时会去掉后缀放在主程序旁边
+
+set -e
+
+SCRIPT_DIR="$(cd "$(dirname "$0")" && pwd)"
+TAURI_DIR="$SCRIPT_DIR/../src-tauri"
+
+# tauri build 会通过 TAURI_ENV_TARGET_TRIPLE 传入目标平台，单独运行时使用本机平台
+TARGET="${TAURI_ENV_TARGET_TRIPLE:-$(rustc -vV | sed -n 's/^host: //p')}"
+
+cd "$TAURI_DIR"
+# 客户端不需要托盘界面，不链接 Tauri
+cargo build --release --no-default-features --bin focus-guard-notify --target "$TARGET"
+
+mkdir -p binaries
+cp "target/$TARGET/release/focus-guard-notify" "binaries/focus-guard-notify-$TARGET"
+echo "focus-guard-notify -> binaries/focus-guard-notify-$TARGET"
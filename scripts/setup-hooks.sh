@@ -2,6 +2,9 @@
 
 # Focus Guard - CLI Hooks 配置脚本
 # 自动配置 Claude Code、Gemini CLI、Codex CLI 的 hooks
+#
+# 推荐改用内置的安装命令（结构化合并、可卸载、支持 --dry-run）:
+#   focus-guard hooks install|uninstall|status [--cli claude|gemini|codex] [--dry-run]
 
 set -e
 
@@ -230,4 +233,4 @@ echo ""
 echo "请重启相应的 CLI 工具以使配置生效。"
 echo ""
 echo "测试方法:"
-echo "  echo '{\"cli\":\"claude\",\"event\":\"stop\",\"session_id\":\"test\",\"cwd\":\"/tmp\"}' | nc -U /tmp/focus-guard.sock"
+echo "  echo '{\"session_id\":\"test\",\"cwd\":\"/tmp\"}' | $NOTIFY_SCRIPT claude stop"
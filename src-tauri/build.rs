@@ -1,3 +1,29 @@
 fn main() {
-    tauri_build::build()
+    // 无界面版本（--no-default-features）不打包，也不需要 tauri-build
+    #[cfg(feature = "tray")]
+    {
+        ensure_notify_sidecar();
+        tauri_build::build()
+    }
+}
+
+/// tauri-build 要求 bundle.externalBin 中的文件在编译时就存在，
+/// 而 focus-guard-notify 本身是这个 crate 的二进制，需要先由 scripts/build-notify-sidecar.sh
+/// 单独编译（无界面版本，不会走到这里）。缺少时直接报错，不放占位文件，以免空文件被打进安装包。
+#[cfg(feature = "tray")]
+fn ensure_notify_sidecar() {
+    let target = std::env::var("TARGET").expect("TARGET is set by cargo");
+    let path = std::path::Path::new("binaries").join(format!("focus-guard-notify-{}", target));
+    println!("cargo:rerun-if-changed={}", path.display());
+    let built = std::fs::metadata(&path)
+        .map(|m| m.len() > 0)
+        .unwrap_or(false);
+    if !built {
+        let message = format!(
+            "notify sidecar {} is missing or empty, run scripts/build-notify-sidecar.sh first",
+            path.display()
+        );
+        println!("cargo:warning={}", message);
+        panic!("{}", message);
+    }
 }
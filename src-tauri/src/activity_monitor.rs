@@ -1,7 +1,7 @@
+use crate::clock::{SharedClock, SystemClock};
 use rdev::{listen, Event, EventType};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 #[cfg(target_os = "macos")]
 use std::process::Command;
@@ -72,25 +72,29 @@ pub struct ActivityMonitor {
     last_activity_type: Arc<Mutex<LastActivity>>,
     event_count: Arc<AtomicU64>,           // 事件计数器，用于调试
     monitoring_start_time: Arc<AtomicU64>, // 开始监控的时间
+    clock: SharedClock,
 }
 
 impl ActivityMonitor {
     pub fn new() -> Self {
+        Self::with_clock(SystemClock::shared())
+    }
+
+    /// 使用指定时钟创建
+    pub fn with_clock(clock: SharedClock) -> Self {
         Self {
-            last_activity: Arc::new(AtomicU64::new(Self::current_timestamp())),
+            last_activity: Arc::new(AtomicU64::new(clock.unix_secs())),
             is_running: Arc::new(AtomicBool::new(false)),
             is_monitoring: Arc::new(AtomicBool::new(false)),
             last_activity_type: Arc::new(Mutex::new(LastActivity::None)),
             event_count: Arc::new(AtomicU64::new(0)),
             monitoring_start_time: Arc::new(AtomicU64::new(0)),
+            clock,
         }
     }
 
-    fn current_timestamp() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs()
+    fn current_timestamp(&self) -> u64 {
+        self.clock.unix_secs()
     }
 
     /// 启动监听线程（后台运行，但只在 is_monitoring 为 true 时记录活动）
@@ -105,6 +109,7 @@ impl ActivityMonitor {
         let is_monitoring = self.is_monitoring.clone();
         let last_activity_type = self.last_activity_type.clone();
         let event_count = self.event_count.clone();
+        let clock = self.clock.clone();
 
         std::thread::spawn(move || {
             println!("[ActivityMonitor] 监听线程已启动");
@@ -130,14 +135,11 @@ impl ActivityMonitor {
                     if let Some(activity) = activity_type {
                         let count = event_count.fetch_add(1, Ordering::SeqCst) + 1;
                         *last_activity_type.lock().unwrap() = activity;
-                        let now = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs();
+                        let now = clock.unix_secs();
                         last_activity.store(now, Ordering::SeqCst);
 
                         // 每100个事件打印一次日志
-                        if count % 100 == 0 {
+                        if count.is_multiple_of(100) {
                             println!("[ActivityMonitor] 已接收 {} 个事件，最后活动时间: {}", count, now);
                         }
                     }
@@ -162,7 +164,8 @@ impl ActivityMonitor {
         self.is_monitoring.store(true, Ordering::SeqCst);
         *self.last_activity_type.lock().unwrap() = LastActivity::None;
         self.event_count.store(0, Ordering::SeqCst);
-        self.monitoring_start_time.store(Self::current_timestamp(), Ordering::SeqCst);
+        self.monitoring_start_time
+            .store(self.current_timestamp(), Ordering::SeqCst);
         // 不再调用 reset_activity()，让 last_activity 保持之前的值
         // 这样如果没有收到任何事件，has_activity_since_monitoring_started() 会返回 false
     }
@@ -216,16 +219,17 @@ impl ActivityMonitor {
         }
 
         // 检查最后活动距今是否超过指定秒数
-        let now = Self::current_timestamp();
+        let now = self.current_timestamp();
         let inactive = now.saturating_sub(last) >= secs;
         println!("[ActivityMonitor] 检查活动状态：last={}, now={}, secs={}, inactive={}", last, now, secs, inactive);
         inactive
     }
 
     /// 重置最后活动时间为当前时间
+    #[allow(dead_code)]
     pub fn reset_activity(&self) {
         self.last_activity
-            .store(Self::current_timestamp(), Ordering::SeqCst);
+            .store(self.current_timestamp(), Ordering::SeqCst);
     }
 
     /// 获取监控期间的事件数量
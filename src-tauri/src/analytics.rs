@@ -0,0 +1,396 @@
+//! 响应延迟统计：CLI 等待输入后，用户多久才回来继续
+//!
+//! 从历史记录中的状态转换计算：会话从 Working 变为 WaitingInput/Idle 开始计时，
+//! 再次变为 Working 时结束；等待中直接退出的会话不计入。按本地日期分组。
+
+use crate::history::{HistoryRecord, HistoryStore};
+use crate::state_manager::{make_state_key, CliState};
+use chrono::{Days, Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+/// 一次等待
+#[derive(Debug, Clone, Serialize)]
+pub struct WaitSample {
+    pub key: String,
+    pub cli: String,
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub started_at: u64,
+    pub secs: u64,
+}
+
+/// 一组等待时长的统计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencyStats {
+    pub count: usize,
+    pub median_secs: u64,
+    pub p90_secs: u64,
+    pub total_secs: u64,
+}
+
+impl LatencyStats {
+    pub fn from_secs(mut secs: Vec<u64>) -> Self {
+        if secs.is_empty() {
+            return Self::default();
+        }
+        secs.sort_unstable();
+        Self {
+            count: secs.len(),
+            median_secs: percentile(&secs, 50),
+            p90_secs: percentile(&secs, 90),
+            total_secs: secs.iter().sum(),
+        }
+    }
+}
+
+/// 按项目或会话分组的统计
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupLatency {
+    pub label: String,
+    pub stats: LatencyStats,
+}
+
+/// 一天的统计
+#[derive(Debug, Clone, Serialize)]
+pub struct DayLatency {
+    pub date: String, // 本地日期 YYYY-MM-DD
+    pub overall: LatencyStats,
+    pub by_project: Vec<GroupLatency>,
+    pub by_session: Vec<GroupLatency>,
+}
+
+/// 最近若干天的统计，最新的一天在前
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyReport {
+    pub days: Vec<DayLatency>,
+}
+
+/// 排序后的最近秩百分位
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn is_waiting(state: CliState) -> bool {
+    matches!(state, CliState::WaitingInput | CliState::Idle)
+}
+
+/// 从状态转换中提取每次等待
+pub fn collect_waits(records: &[HistoryRecord]) -> Vec<WaitSample> {
+    // 正在等待的会话：key -> 开始时间
+    let mut waiting: HashMap<String, u64> = HashMap::new();
+    let mut samples = Vec::new();
+
+    for record in records {
+        let HistoryRecord::Transition {
+            ts,
+            cli,
+            session_id,
+            cwd,
+            from,
+            to,
+        } = record
+        else {
+            continue;
+        };
+        let key = make_state_key(cli, session_id.as_deref());
+
+        if is_waiting(*to) && !is_waiting(*from) {
+            waiting.insert(key, *ts);
+        } else if *to == CliState::Working {
+            if let Some(started_at) = waiting.remove(&key) {
+                samples.push(WaitSample {
+                    key,
+                    cli: cli.clone(),
+                    session_id: session_id.clone(),
+                    cwd: cwd.clone(),
+                    started_at,
+                    secs: ts.saturating_sub(started_at),
+                });
+            }
+        } else if *to == CliState::Offline {
+            // 等待中退出，不算用户响应
+            waiting.remove(&key);
+        }
+    }
+    samples
+}
+
+/// 本地日期 0 点的 Unix 时间戳
+pub fn local_day_start(date: NaiveDate) -> u64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.timestamp().max(0) as u64)
+        .unwrap_or(0)
+}
+
+/// Unix 时间戳对应的本地日期
+pub fn local_date(ts: u64) -> NaiveDate {
+    Local
+        .timestamp_opt(ts as i64, 0)
+        .single()
+        .map(|t| t.date_naive())
+        .unwrap_or_default()
+}
+
+/// 按开始等待的本地日期分组统计
+pub fn latency_report(records: &[HistoryRecord]) -> LatencyReport {
+    let mut by_day: BTreeMap<NaiveDate, Vec<WaitSample>> = BTreeMap::new();
+    for sample in collect_waits(records) {
+        by_day
+            .entry(local_date(sample.started_at))
+            .or_default()
+            .push(sample);
+    }
+
+    let days = by_day
+        .into_iter()
+        .rev()
+        .map(|(date, samples)| DayLatency {
+            date: date.format("%Y-%m-%d").to_string(),
+            overall: LatencyStats::from_secs(samples.iter().map(|s| s.secs).collect()),
+            by_project: group_by(&samples, |s| {
+                s.cwd.clone().unwrap_or_else(|| "(unknown)".to_string())
+            }),
+            by_session: group_by(&samples, |s| s.key.clone()),
+        })
+        .collect();
+    LatencyReport { days }
+}
+
+/// 分组统计，按中位数从高到低排序（最被冷落的排前面）
+fn group_by(samples: &[WaitSample], label: impl Fn(&WaitSample) -> String) -> Vec<GroupLatency> {
+    let mut groups: HashMap<String, Vec<u64>> = HashMap::new();
+    for sample in samples {
+        groups.entry(label(sample)).or_default().push(sample.secs);
+    }
+    let mut result: Vec<GroupLatency> = groups
+        .into_iter()
+        .map(|(label, secs)| GroupLatency {
+            label,
+            stats: LatencyStats::from_secs(secs),
+        })
+        .collect();
+    result.sort_by(|a, b| {
+        b.stats
+            .median_secs
+            .cmp(&a.stats.median_secs)
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    result
+}
+
+/// 最近 days 天（含今天）的统计，now 为当前 Unix 时间戳
+pub fn recent_report(history: &HistoryStore, now: u64, days: u32) -> LatencyReport {
+    let today = local_date(now);
+    let first = today - Days::new(u64::from(days.max(1) - 1));
+    latency_report(&history.read_since(local_day_start(first)))
+}
+
+/// 一段时间内单个项目的 Agent 工作/等待时长
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectTime {
+    pub project: String, // 目录名
+    pub cwd: Option<String>,
+    pub working_secs: u64,
+    pub waiting_secs: u64,
+}
+
+/// 统计 [start, end) 内各项目处于 Working 和等待（WaitingInput/Idle/Stalled）状态的时长
+/// 从会话在区间内的第一条转换开始计时（之前运行了多久无法确定），区间结束时仍未结束的状态计到 end
+pub fn project_times(records: &[HistoryRecord], start: u64, end: u64) -> Vec<ProjectTime> {
+    // 会话 key -> (当前状态, 开始时间, cwd)
+    let mut open: HashMap<String, (CliState, u64, Option<String>)> = HashMap::new();
+    let mut totals: BTreeMap<Option<String>, (u64, u64)> = BTreeMap::new();
+
+    let mut add = |state: CliState, from: u64, to: u64, cwd: &Option<String>| {
+        let secs = to.saturating_sub(from);
+        let entry = totals.entry(cwd.clone()).or_default();
+        match state {
+            CliState::Working => entry.0 += secs,
+            // 卡住的会话同样在等用户处理
+            CliState::WaitingInput | CliState::Idle | CliState::Stalled => entry.1 += secs,
+            CliState::Offline => {}
+        }
+    };
+
+    for record in records {
+        let HistoryRecord::Transition {
+            ts,
+            cli,
+            session_id,
+            cwd,
+            to,
+            ..
+        } = record
+        else {
+            continue;
+        };
+        if *ts < start || *ts >= end {
+            continue;
+        }
+        let key = make_state_key(cli, session_id.as_deref());
+        let mut cwd = cwd.clone();
+        if let Some((state, since, last_cwd)) = open.remove(&key) {
+            cwd = cwd.or(last_cwd);
+            add(state, since, *ts, &cwd);
+        }
+        open.insert(key, (*to, *ts, cwd));
+    }
+    for (state, since, cwd) in open.values() {
+        add(*state, *since, end, cwd);
+    }
+
+    let mut result: Vec<ProjectTime> = totals
+        .into_iter()
+        .filter(|(_, (working, waiting))| working + waiting > 0)
+        .map(|(cwd, (working_secs, waiting_secs))| ProjectTime {
+            project: project_name(cwd.as_deref()),
+            cwd,
+            working_secs,
+            waiting_secs,
+        })
+        .collect();
+    result.sort_by_key(|p| Reverse(p.working_secs + p.waiting_secs));
+    result
+}
+
+/// 项目名取 cwd 的最后一级目录
+pub fn project_name(cwd: Option<&str>) -> String {
+    match cwd {
+        Some(path) => std::path::Path::new(path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(path)
+            .to_string(),
+        None => "(unknown)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(
+        ts: u64,
+        session: &str,
+        cwd: &str,
+        from: CliState,
+        to: CliState,
+    ) -> HistoryRecord {
+        HistoryRecord::Transition {
+            ts,
+            cli: "claude".to_string(),
+            session_id: Some(session.to_string()),
+            cwd: Some(cwd.to_string()),
+            from,
+            to,
+        }
+    }
+
+    /// 会话 session 在 start 开始等待，等待 secs 秒后恢复工作
+    fn wait(start: u64, secs: u64, session: &str, cwd: &str) -> [HistoryRecord; 2] {
+        [
+            transition(
+                start,
+                session,
+                cwd,
+                CliState::Working,
+                CliState::WaitingInput,
+            ),
+            transition(
+                start + secs,
+                session,
+                cwd,
+                CliState::WaitingInput,
+                CliState::Working,
+            ),
+        ]
+    }
+
+    fn day(d: u32) -> u64 {
+        local_day_start(NaiveDate::from_ymd_opt(2024, 3, d).unwrap())
+    }
+
+    #[test]
+    fn waits_pair_with_the_next_working_transition() {
+        let records = [
+            transition(100, "a", "/p", CliState::Working, CliState::WaitingInput),
+            // 变为 Idle 仍是同一次等待
+            transition(160, "a", "/p", CliState::WaitingInput, CliState::Idle),
+            transition(
+                100 + 90,
+                "b",
+                "/q",
+                CliState::Working,
+                CliState::WaitingInput,
+            ),
+            transition(400, "a", "/p", CliState::Idle, CliState::Working),
+            // 没有开始等待的 Working 不产生样本
+            transition(500, "c", "/r", CliState::Idle, CliState::Working),
+        ];
+        let samples = collect_waits(&records);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].key, "claude:a");
+        assert_eq!(samples[0].started_at, 100);
+        assert_eq!(samples[0].secs, 300);
+        // 会话 b 到最后仍在等待，不计入
+        assert!(samples.iter().all(|s| s.key != "claude:b"));
+    }
+
+    #[test]
+    fn exiting_while_waiting_is_not_a_response() {
+        let records = [
+            transition(100, "a", "/p", CliState::Working, CliState::WaitingInput),
+            transition(200, "a", "/p", CliState::WaitingInput, CliState::Offline),
+            transition(300, "a", "/p", CliState::Offline, CliState::Working),
+        ];
+        assert!(collect_waits(&records).is_empty());
+    }
+
+    #[test]
+    fn stats_use_nearest_rank_percentiles() {
+        let stats = LatencyStats::from_secs((1..=10).rev().collect());
+        assert_eq!(stats.count, 10);
+        assert_eq!(stats.median_secs, 5);
+        assert_eq!(stats.p90_secs, 9);
+        assert_eq!(stats.total_secs, 55);
+
+        let single = LatencyStats::from_secs(vec![42]);
+        assert_eq!((single.median_secs, single.p90_secs), (42, 42));
+        assert_eq!(LatencyStats::from_secs(Vec::new()).count, 0);
+    }
+
+    #[test]
+    fn report_groups_by_day_and_project() {
+        let noon = 12 * 3600;
+        let records: Vec<HistoryRecord> = [
+            wait(day(4) + noon, 30, "a", "/work/app"),
+            wait(day(4) + noon + 100, 90, "a", "/work/app"),
+            wait(day(4) + noon + 200, 600, "b", "/work/lib"),
+            // 跨过午夜的等待算在开始的那一天
+            wait(day(5) - 60, 120, "a", "/work/app"),
+            wait(day(5) + noon, 10, "a", "/work/app"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let report = latency_report(&records);
+        let dates: Vec<&str> = report.days.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, ["2024-03-05", "2024-03-04"]);
+
+        let day4 = &report.days[1];
+        assert_eq!(day4.overall.count, 4);
+        let projects: Vec<&str> = day4.by_project.iter().map(|g| g.label.as_str()).collect();
+        // 中位数高的项目排在前面
+        assert_eq!(projects, ["/work/lib", "/work/app"]);
+        assert_eq!(day4.by_project[1].stats.count, 3);
+        assert_eq!(day4.by_session[0].label, "claude:b");
+        assert_eq!(report.days[0].overall.total_secs, 10);
+    }
+}
@@ -0,0 +1,111 @@
+//! Focus Guard hook 客户端，替代 scripts/focus-guard-notify
+//!
+//! 用法:
+//!   focus-guard-notify <cli_name> <event> [hook_json]
+//!   echo '{"session_id":"xxx","cwd":"/path"}' | focus-guard-notify <cli_name> <event>
+//!
+//! Focus Guard 未运行时静默退出（返回 0），不影响 CLI 工具本身。
+
+use focus_guard_lib::ipc_server::{socket_path, CliEvent, CliMessage};
+use focus_guard_lib::process_inspector::find_cli_ancestor;
+use std::io::{IsTerminal, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 连接和写入的超时时间，hook 不能拖慢 CLI
+const IO_TIMEOUT: Duration = Duration::from_millis(500);
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        print_usage();
+        std::process::exit(1);
+    }
+
+    let mut msg = CliMessage::new(args[0].as_str(), CliEvent::from(args[1].clone()));
+    // hook 由 CLI 经 shell 启动，上报真正的 CLI 进程而不是自己（很快就会退出）
+    msg.pid = find_cli_ancestor(std::process::id());
+    msg.timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs());
+
+    // hook 数据：优先使用第三个参数，否则从 stdin 读取
+    let hook_input = match args.get(2) {
+        Some(json) => Some(json.clone()),
+        None => read_stdin(),
+    };
+    if let Some(input) = hook_input.as_deref().and_then(parse_hook_input) {
+        // Codex 的 notify 数据用 thread-id 标识会话
+        msg.session_id =
+            string_field(&input, "session_id").or_else(|| string_field(&input, "thread-id"));
+        msg.cwd = string_field(&input, "cwd");
+    }
+    if msg.cwd.is_none() {
+        msg.cwd = std::env::current_dir()
+            .ok()
+            .map(|p| p.to_string_lossy().to_string());
+    }
+
+    // 发送失败时静默处理
+    let _ = send(&msg);
+}
+
+fn print_usage() {
+    eprintln!("Usage: focus-guard-notify <cli_name> <event> [hook_json]");
+    eprintln!("  cli_name: claude, gemini, codex");
+    eprintln!("  event: {}", CliEvent::KNOWN.join(", "));
+    eprintln!();
+    eprintln!("Hook JSON with session_id and cwd is read from stdin when not given as an argument");
+}
+
+/// 读取 stdin（终端输入时跳过，避免阻塞）
+fn read_stdin() -> Option<String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return None;
+    }
+    let mut data = String::new();
+    stdin.read_to_string(&mut data).ok()?;
+    if data.trim().is_empty() {
+        None
+    } else {
+        Some(data)
+    }
+}
+
+fn parse_hook_input(data: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(data.trim()).ok()
+}
+
+fn string_field(input: &serde_json::Value, key: &str) -> Option<String> {
+    input
+        .get(key)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+/// 在超时时间内连接 socket
+fn connect() -> Option<UnixStream> {
+    let path = socket_path();
+    if !path.exists() {
+        return None;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(UnixStream::connect(path));
+    });
+    rx.recv_timeout(IO_TIMEOUT).ok()?.ok()
+}
+
+fn send(msg: &CliMessage) -> Option<()> {
+    let mut stream = connect()?;
+    stream.set_write_timeout(Some(IO_TIMEOUT)).ok()?;
+
+    let mut line = serde_json::to_string(msg).ok()?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).ok()
+}
@@ -0,0 +1,532 @@
+//! 通知渠道：桌面通知、HTTP webhook、ntfy、Gotify、外部命令
+//!
+//! 配置中的每个渠道可以指定接收哪些事件（CLI 等待、会话卡住、久坐提醒、更新），
+//! 同一事件可以同时发到多个渠道，比如离开座位时让手机也收到等待提醒。
+//! 网络请求在后台线程中发送，不阻塞状态管理。
+
+use crate::notification::send_desktop_notification;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// HTTP 请求和外部命令的超时时间
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+/// 等待外部命令退出时的检查间隔
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 可路由的通知事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    /// CLI 等待输入
+    CliWaiting,
+    /// 会话可能卡住了
+    CliStalled,
+    /// 久坐提醒
+    SittingReminder,
+    /// 发现新版本
+    Update,
+    /// 免打扰结束后汇总期间暂缓的提醒
+    QuietSummary,
+}
+
+impl NotifyEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotifyEvent::CliWaiting => "cli_waiting",
+            NotifyEvent::CliStalled => "cli_stalled",
+            NotifyEvent::SittingReminder => "sitting_reminder",
+            NotifyEvent::Update => "update",
+            NotifyEvent::QuietSummary => "quiet_summary",
+        }
+    }
+}
+
+/// 一条待发送的通知
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub event: NotifyEvent,
+    pub title: String,
+    pub body: String,
+    /// 桌面通知是否播放提示音（其他渠道忽略）
+    pub with_sound: bool,
+}
+
+impl Notification {
+    pub fn new(event: NotifyEvent, title: &str, body: &str, with_sound: bool) -> Self {
+        Self {
+            event,
+            title: title.to_string(),
+            body: body.to_string(),
+            with_sound,
+        }
+    }
+}
+
+/// 通知渠道
+pub trait NotificationChannel: Send + Sync {
+    fn send(&self, notification: &Notification) -> Result<(), String>;
+}
+
+/// 渠道类型及其参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelKind {
+    /// 系统通知中心（托盘模式走 Tauri，无界面模式走 osascript/notify-send）
+    Desktop,
+    /// POST JSON：{"event","title","body","timestamp"}
+    Webhook {
+        url: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// ntfy（https://ntfy.sh 或自建服务）
+    Ntfy {
+        server: String,
+        topic: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        priority: Option<u8>,
+    },
+    /// Gotify，token 为应用 token
+    Gotify {
+        server: String,
+        token: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        priority: Option<u8>,
+    },
+    /// 运行外部命令，通知内容通过环境变量
+    /// FOCUS_GUARD_EVENT / FOCUS_GUARD_TITLE / FOCUS_GUARD_BODY 传入
+    Command {
+        program: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
+}
+
+/// 配置中的一个渠道
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelConfig {
+    /// 名称，只用于日志
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: ChannelKind,
+    /// 接收的事件，为空时接收全部
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<NotifyEvent>,
+}
+
+impl ChannelConfig {
+    /// 是否为桌面以外的渠道（等待提醒阶梯中的 push 一步只发到这些渠道）
+    pub fn is_remote(&self) -> bool {
+        self.kind != ChannelKind::Desktop
+    }
+
+    /// 是否接收该事件
+    pub fn accepts(&self, event: NotifyEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// 默认只有桌面通知
+pub fn default_channels() -> Vec<ChannelConfig> {
+    vec![ChannelConfig {
+        id: "desktop".to_string(),
+        kind: ChannelKind::Desktop,
+        events: Vec::new(),
+    }]
+}
+
+/// 把通知发到所有接收该事件的渠道，desktop 为当前模式下的桌面通知实现
+/// 在后台线程中依次发送，单个渠道失败只记录日志
+pub fn dispatch(
+    configs: &[ChannelConfig],
+    desktop: Arc<dyn NotificationChannel>,
+    notification: Notification,
+) {
+    let targets: Vec<(String, Arc<dyn NotificationChannel>)> = configs
+        .iter()
+        .filter(|c| c.accepts(notification.event))
+        .map(|c| {
+            let channel: Arc<dyn NotificationChannel> = match &c.kind {
+                ChannelKind::Desktop => desktop.clone(),
+                kind => Arc::new(RemoteChannel(kind.clone())),
+            };
+            (c.id.clone(), channel)
+        })
+        .collect();
+    if targets.is_empty() {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        for (id, channel) in targets {
+            if let Err(e) = channel.send(&notification) {
+                eprintln!("Notification channel {} failed: {}", id, e);
+            }
+        }
+    });
+}
+
+/// 无界面模式的桌面通知
+pub struct DesktopChannel;
+
+impl NotificationChannel for DesktopChannel {
+    fn send(&self, n: &Notification) -> Result<(), String> {
+        send_desktop_notification(&n.title, &n.body, n.with_sound)
+    }
+}
+
+/// 桌面以外的渠道
+struct RemoteChannel(ChannelKind);
+
+impl NotificationChannel for RemoteChannel {
+    fn send(&self, n: &Notification) -> Result<(), String> {
+        match &self.0 {
+            ChannelKind::Desktop => Ok(()),
+            ChannelKind::Webhook { url, headers } => {
+                let payload = serde_json::json!({
+                    "event": n.event.as_str(),
+                    "title": n.title,
+                    "body": n.body,
+                    "timestamp": unix_secs(),
+                });
+                let mut request = http_client()?.post(url).json(&payload);
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                check_response(request.send())
+            }
+            ChannelKind::Ntfy {
+                server,
+                topic,
+                token,
+                priority,
+            } => {
+                // 用 JSON 发布，标题可以包含非 ASCII 字符
+                let mut payload = serde_json::json!({
+                    "topic": topic,
+                    "title": n.title,
+                    "message": n.body,
+                    "tags": [n.event.as_str()],
+                });
+                if let Some(p) = priority {
+                    payload["priority"] = serde_json::json!(p);
+                }
+                let mut request = http_client()?
+                    .post(server.trim_end_matches('/'))
+                    .json(&payload);
+                if let Some(token) = token {
+                    request = request.bearer_auth(token);
+                }
+                check_response(request.send())
+            }
+            ChannelKind::Gotify {
+                server,
+                token,
+                priority,
+            } => {
+                let payload = serde_json::json!({
+                    "title": n.title,
+                    "message": n.body,
+                    "priority": priority.unwrap_or(5),
+                });
+                let url = format!("{}/message", server.trim_end_matches('/'));
+                let request = http_client()?
+                    .post(url)
+                    .header("X-Gotify-Key", token)
+                    .json(&payload);
+                check_response(request.send())
+            }
+            ChannelKind::Command { program, args } => run_command(program, args, n, HTTP_TIMEOUT),
+        }
+    }
+}
+
+/// 运行外部命令，超时未退出时结束它
+fn run_command(
+    program: &str,
+    args: &[String],
+    n: &Notification,
+    timeout: Duration,
+) -> Result<(), String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .env("FOCUS_GUARD_EVENT", n.event.as_str())
+        .env("FOCUS_GUARD_TITLE", &n.title)
+        .env("FOCUS_GUARD_BODY", &n.body)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+
+    // 在单独的线程中读取 stderr，避免输出过多时填满管道让命令阻塞
+    let stderr = child.stderr.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = pipe.read_to_string(&mut text);
+            text
+        })
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child
+            .try_wait()
+            .map_err(|e| format!("{}: {}", program, e))?
+        {
+            Some(status) => break status,
+            None if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{} timed out after {:?}", program, timeout));
+            }
+            None => std::thread::sleep(COMMAND_POLL_INTERVAL),
+        }
+    };
+    if status.success() {
+        return Ok(());
+    }
+
+    let stderr = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    let mut error = format!("{} exited with {}", program, status);
+    if !stderr.trim().is_empty() {
+        error = format!("{}: {}", error, stderr.trim());
+    }
+    Err(error)
+}
+
+fn http_client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn check_response(result: reqwest::Result<reqwest::blocking::Response>) -> Result<(), String> {
+    let response = result.map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", response.status()))
+    }
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// 收到的一个 HTTP 请求
+    struct Request {
+        method: String,
+        path: String,
+        /// 头部名称统一为小写
+        headers: HashMap<String, String>,
+        body: Value,
+    }
+
+    /// 在 127.0.0.1 上接收一个请求并返回 200，返回 (服务地址, 收到的请求)
+    fn serve_once() -> (String, std::thread::JoinHandle<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let mut parts = line.split_whitespace();
+            let method = parts.next().unwrap().to_string();
+            let path = parts.next().unwrap().to_string();
+
+            let mut headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(':').unwrap();
+                headers.insert(name.to_lowercase(), value.trim().to_string());
+            }
+
+            let length: usize = headers["content-length"].parse().unwrap();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+
+            Request {
+                method,
+                path,
+                headers,
+                body: serde_json::from_slice(&body).unwrap(),
+            }
+        });
+        (url, handle)
+    }
+
+    fn notification() -> Notification {
+        Notification::new(NotifyEvent::CliWaiting, "Claude - 项目", "等待输入", true)
+    }
+
+    #[test]
+    fn webhook_posts_event_json_with_headers() {
+        let (url, server) = serve_once();
+        let mut headers = BTreeMap::new();
+        headers.insert("X-Token".to_string(), "secret".to_string());
+        let channel = RemoteChannel(ChannelKind::Webhook {
+            url: format!("{}/hook", url),
+            headers,
+        });
+
+        let before = unix_secs();
+        channel.send(&notification()).unwrap();
+        let mut request = server.join().unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/hook");
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(request.headers["x-token"], "secret");
+
+        let timestamp = request.body["timestamp"].as_u64().unwrap();
+        assert!(timestamp >= before && timestamp <= unix_secs());
+        request.body.as_object_mut().unwrap().remove("timestamp");
+        assert_eq!(
+            request.body,
+            json!({
+                "event": "cli_waiting",
+                "title": "Claude - 项目",
+                "body": "等待输入",
+            })
+        );
+    }
+
+    #[test]
+    fn ntfy_publishes_json_to_server_root() {
+        let (url, server) = serve_once();
+        let channel = RemoteChannel(ChannelKind::Ntfy {
+            server: format!("{}/", url),
+            topic: "focus".to_string(),
+            token: Some("tk_abc".to_string()),
+            priority: Some(4),
+        });
+
+        channel.send(&notification()).unwrap();
+        let request = server.join().unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/");
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(request.headers["authorization"], "Bearer tk_abc");
+        assert_eq!(
+            request.body,
+            json!({
+                "topic": "focus",
+                "title": "Claude - 项目",
+                "message": "等待输入",
+                "tags": ["cli_waiting"],
+                "priority": 4,
+            })
+        );
+    }
+
+    #[test]
+    fn ntfy_without_token_or_priority() {
+        let (url, server) = serve_once();
+        let channel = RemoteChannel(ChannelKind::Ntfy {
+            server: url,
+            topic: "focus".to_string(),
+            token: None,
+            priority: None,
+        });
+
+        channel.send(&notification()).unwrap();
+        let request = server.join().unwrap();
+
+        assert!(!request.headers.contains_key("authorization"));
+        assert_eq!(
+            request.body,
+            json!({
+                "topic": "focus",
+                "title": "Claude - 项目",
+                "message": "等待输入",
+                "tags": ["cli_waiting"],
+            })
+        );
+    }
+
+    #[test]
+    fn gotify_posts_message_with_app_token() {
+        let (url, server) = serve_once();
+        let channel = RemoteChannel(ChannelKind::Gotify {
+            server: url,
+            token: "AbCd".to_string(),
+            priority: None,
+        });
+
+        channel.send(&notification()).unwrap();
+        let request = server.join().unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/message");
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(request.headers["x-gotify-key"], "AbCd");
+        assert_eq!(
+            request.body,
+            json!({
+                "title": "Claude - 项目",
+                "message": "等待输入",
+                "priority": 5,
+            })
+        );
+    }
+
+    #[test]
+    fn command_receives_notification_in_env() {
+        let args = vec![
+            "-c".to_string(),
+            r#"test "$FOCUS_GUARD_EVENT" = cli_waiting && test "$FOCUS_GUARD_BODY" = 等待输入"#
+                .to_string(),
+        ];
+        run_command("sh", &args, &notification(), HTTP_TIMEOUT).unwrap();
+    }
+
+    #[test]
+    fn command_failure_includes_stderr() {
+        let args = vec!["-c".to_string(), "echo broken >&2; exit 3".to_string()];
+        let error = run_command("sh", &args, &notification(), HTTP_TIMEOUT).unwrap_err();
+        assert!(error.ends_with(": broken"), "{}", error);
+    }
+
+    #[test]
+    fn command_is_killed_after_timeout() {
+        let args = vec!["30".to_string()];
+        let started = Instant::now();
+        let error =
+            run_command("sleep", &args, &notification(), Duration::from_millis(200)).unwrap_err();
+        assert!(error.contains("timed out"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,405 @@
+//! 受监控的 CLI 列表，可在配置中增删
+//!
+//! 每一项说明如何从进程识别这个 CLI（进程名精确匹配、进程名正则、命令行正则、
+//! 解释器运行的脚本）、菜单中显示的名称和图标，以及是否已配置 hooks。
+//!
+//! 很多 CLI 以 `node .../cli.js` 或 `python -m aider` 运行，进程名只是解释器，
+//! 需要看命令行里的脚本路径或模块名才能认出来。
+
+use crate::state_manager::CliStatus;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 进程匹配规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProcessMatcher {
+    /// 进程名完全相同（不区分大小写）
+    Exact { name: String },
+    /// 正则匹配进程名
+    Regex { pattern: String },
+    /// 正则匹配完整命令行（参数以空格连接）
+    Argv { pattern: String },
+    /// 进程是解释器（node/python/bun/deno）时，正则匹配它运行的脚本路径或 `-m` 模块名
+    Script { pattern: String },
+}
+
+/// 一个受监控的 CLI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CliDefinition {
+    /// 标识，同时作为会话的 CLI 名（hooks 上报的 cli 字段应与之一致）
+    pub id: String,
+    pub display_name: String,
+    pub matchers: Vec<ProcessMatcher>,
+    /// 已配置 hooks：状态由 hooks 上报，兜底检测不再为它创建会话，只检测退出
+    #[serde(default)]
+    pub hooks_expected: bool,
+    /// 菜单中显示在名称前的图标（如 emoji）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+impl CliDefinition {
+    /// 按进程名精确匹配 id，解释器运行时按脚本匹配 script
+    fn new(id: &str, display_name: &str, script: &str, hooks_expected: bool) -> Self {
+        Self {
+            id: id.to_string(),
+            display_name: display_name.to_string(),
+            matchers: vec![
+                ProcessMatcher::Exact {
+                    name: id.to_string(),
+                },
+                ProcessMatcher::Script {
+                    pattern: script.to_string(),
+                },
+            ],
+            hooks_expected,
+            icon: None,
+        }
+    }
+}
+
+/// 默认监控的 CLI（Claude Code、Gemini CLI 已有 hooks，其余只能靠进程检测）
+/// 脚本规则覆盖 npm 全局安装的包路径和 bin 目录下的同名入口
+pub fn default_definitions() -> Vec<CliDefinition> {
+    vec![
+        CliDefinition::new(
+            "claude",
+            "Claude",
+            r"@anthropic-ai[/\\]claude-code|(^|[/\\])claude$",
+            true,
+        ),
+        CliDefinition::new(
+            "gemini",
+            "Gemini",
+            r"@google[/\\]gemini-cli|(^|[/\\])gemini$",
+            true,
+        ),
+        CliDefinition::new(
+            "codex",
+            "Codex",
+            r"@openai[/\\]codex|(^|[/\\])codex$",
+            false,
+        ),
+        CliDefinition::new("aider", "Aider", r"(^|[/\\])aider(\.main)?$", false),
+    ]
+}
+
+/// 常见的脚本解释器（进程名，不区分大小写）
+fn is_interpreter(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name);
+    matches!(name, "node" | "nodejs" | "bun" | "deno") || name.starts_with("python")
+}
+
+/// 解释器运行的脚本路径或 `-m` 模块名；不是解释器、或执行的是 -c/-e 代码时返回 None
+pub fn script_target<'a>(name: &str, argv: &'a [String]) -> Option<&'a str> {
+    if !is_interpreter(name) {
+        return None;
+    }
+    let mut args = argv.iter().skip(1).map(String::as_str);
+    while let Some(arg) = args.next() {
+        match arg {
+            "-m" => return args.next(),
+            "-c" | "-e" | "--eval" | "-p" | "--print" => return None,
+            // 带值的选项，跳过它的值
+            "-r" | "--require" | "--import" | "--loader" | "-W" | "-X" => {
+                args.next();
+            }
+            // deno run / bun run 后面才是脚本
+            "run" => continue,
+            _ if arg.starts_with('-') => continue,
+            _ => return Some(arg),
+        }
+    }
+    None
+}
+
+/// 编译后的匹配规则
+enum CompiledMatcher {
+    Exact(String),
+    Name(Regex),
+    Argv(Regex),
+    Script(Regex),
+}
+
+impl CompiledMatcher {
+    fn matches(&self, name: &str, argv: &[String]) -> bool {
+        match self {
+            CompiledMatcher::Exact(n) => name.eq_ignore_ascii_case(n),
+            CompiledMatcher::Name(re) => re.is_match(name),
+            CompiledMatcher::Argv(re) => re.is_match(&argv.join(" ")),
+            CompiledMatcher::Script(re) => {
+                script_target(name, argv).is_some_and(|s| re.is_match(s))
+            }
+        }
+    }
+}
+
+/// 编译后的 CLI 列表，用于匹配进程
+pub struct CliRegistry {
+    entries: Vec<(CliDefinition, Vec<CompiledMatcher>)>,
+}
+
+impl CliRegistry {
+    /// 编译配置中的定义，返回注册表和无效规则的说明
+    pub fn compile(definitions: &[CliDefinition]) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut entries = Vec::new();
+
+        for def in definitions {
+            if def.id.trim().is_empty() {
+                errors.push("CLI definition with empty id ignored".to_string());
+                continue;
+            }
+            let mut compiled = Vec::new();
+            for matcher in &def.matchers {
+                let result = match matcher {
+                    ProcessMatcher::Exact { name } => Ok(CompiledMatcher::Exact(name.clone())),
+                    ProcessMatcher::Regex { pattern } => {
+                        Regex::new(pattern).map(CompiledMatcher::Name)
+                    }
+                    ProcessMatcher::Argv { pattern } => {
+                        Regex::new(pattern).map(CompiledMatcher::Argv)
+                    }
+                    ProcessMatcher::Script { pattern } => {
+                        Regex::new(pattern).map(CompiledMatcher::Script)
+                    }
+                };
+                match result {
+                    Ok(m) => compiled.push(m),
+                    Err(e) => errors.push(format!("{}: invalid pattern: {}", def.id, e)),
+                }
+            }
+            entries.push((def.clone(), compiled));
+        }
+        (Self { entries }, errors)
+    }
+
+    /// 按进程名和命令行参数查找对应的 CLI，先定义的优先
+    pub fn match_process(&self, name: &str, argv: &[String]) -> Option<&CliDefinition> {
+        self.entries
+            .iter()
+            .find(|(_, matchers)| matchers.iter().any(|m| m.matches(name, argv)))
+            .map(|(def, _)| def)
+    }
+
+    /// 按 id 查找（不区分大小写）
+    pub fn get(&self, id: &str) -> Option<&CliDefinition> {
+        self.entries
+            .iter()
+            .map(|(def, _)| def)
+            .find(|def| def.id.eq_ignore_ascii_case(id))
+    }
+
+    /// 菜单中显示的会话名称：图标 + 显示名 + 项目目录
+    pub fn menu_label(&self, status: &CliStatus) -> String {
+        let def = match self.get(&status.cli_name) {
+            Some(d) => d,
+            None => return status.display_name.clone(),
+        };
+        let name = match status.cwd.as_deref() {
+            Some(cwd) => {
+                let project = Path::new(cwd)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(cwd);
+                format!("{} - {}", def.display_name, project)
+            }
+            None => def.display_name.clone(),
+        };
+        match &def.icon {
+            Some(icon) => format!("{} {}", icon, name),
+            None => name,
+        }
+    }
+}
+
+impl Default for CliRegistry {
+    fn default() -> Self {
+        Self::compile(&default_definitions()).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn definition(id: &str, matchers: Vec<ProcessMatcher>) -> CliDefinition {
+        CliDefinition {
+            id: id.to_string(),
+            display_name: id.to_uppercase(),
+            matchers,
+            hooks_expected: false,
+            icon: None,
+        }
+    }
+
+    fn matched<'a>(registry: &'a CliRegistry, name: &str, args: &[&str]) -> Option<&'a str> {
+        registry
+            .match_process(name, &argv(args))
+            .map(|def| def.id.as_str())
+    }
+
+    #[test]
+    fn exact_match_ignores_case() {
+        let registry = CliRegistry::default();
+        assert_eq!(matched(&registry, "claude", &["claude"]), Some("claude"));
+        assert_eq!(matched(&registry, "Codex", &["Codex"]), Some("codex"));
+        assert_eq!(
+            matched(&registry, "claude-helper", &["claude-helper"]),
+            None
+        );
+    }
+
+    #[test]
+    fn name_and_argv_patterns() {
+        let (registry, errors) = CliRegistry::compile(&[
+            definition(
+                "goose",
+                vec![ProcessMatcher::Regex {
+                    pattern: "^goose(-cli)?$".to_string(),
+                }],
+            ),
+            definition(
+                "opencode",
+                vec![ProcessMatcher::Argv {
+                    pattern: r"\bopencode --tui\b".to_string(),
+                }],
+            ),
+        ]);
+        assert!(errors.is_empty());
+        assert_eq!(
+            matched(&registry, "goose-cli", &["goose-cli"]),
+            Some("goose")
+        );
+        assert_eq!(matched(&registry, "goosey", &["goosey"]), None);
+        assert_eq!(
+            matched(&registry, "bash", &["/bin/opencode", "--tui"]),
+            Some("opencode")
+        );
+    }
+
+    #[test]
+    fn earlier_definitions_win() {
+        let any = || ProcessMatcher::Regex {
+            pattern: ".*".to_string(),
+        };
+        let (registry, _) = CliRegistry::compile(&[
+            definition("first", vec![any()]),
+            definition("second", vec![any()]),
+        ]);
+        assert_eq!(matched(&registry, "whatever", &["whatever"]), Some("first"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported_and_skipped() {
+        let (registry, errors) = CliRegistry::compile(&[
+            definition(
+                "broken",
+                vec![
+                    ProcessMatcher::Regex {
+                        pattern: "(".to_string(),
+                    },
+                    ProcessMatcher::Exact {
+                        name: "broken".to_string(),
+                    },
+                ],
+            ),
+            definition(" ", vec![]),
+        ]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("broken: invalid pattern:"));
+        assert_eq!(errors[1], "CLI definition with empty id ignored");
+        // 其余有效的规则照常生效
+        assert_eq!(matched(&registry, "broken", &["broken"]), Some("broken"));
+        assert!(registry.get(" ").is_none());
+    }
+
+    #[test]
+    fn menu_label_uses_icon_name_and_project() {
+        let mut icon = definition("claude", vec![]);
+        icon.display_name = "Claude".to_string();
+        icon.icon = Some("🤖".to_string());
+        let (registry, _) = CliRegistry::compile(&[icon]);
+
+        let mut status = CliStatus::new("Claude".to_string());
+        assert_eq!(registry.menu_label(&status), "🤖 Claude");
+        status.cwd = Some("/home/me/work/focus-guard".to_string());
+        assert_eq!(registry.menu_label(&status), "🤖 Claude - focus-guard");
+
+        // 不在列表中的 CLI 使用会话自己的显示名
+        let status = CliStatus::new("kiro".to_string());
+        assert_eq!(registry.menu_label(&status), "Kiro");
+    }
+
+    const CLAUDE_JS: &str = "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js";
+
+    fn target(name: &str, args: &[&str]) -> Option<String> {
+        script_target(name, &argv(args)).map(String::from)
+    }
+
+    #[test]
+    fn script_target_finds_script_or_module() {
+        let found = |s: &str| Some(s.to_string());
+        assert_eq!(target("node", &["node", CLAUDE_JS]), found(CLAUDE_JS));
+        assert_eq!(
+            target("python3", &["python3", "-m", "aider"]),
+            found("aider")
+        );
+        // -r 的值不是脚本
+        assert_eq!(
+            target("node", &["node", "-r", "x", "script.js"]),
+            found("script.js")
+        );
+        assert_eq!(
+            target("deno", &["deno", "run", "-A", "main.ts"]),
+            found("main.ts")
+        );
+        assert_eq!(
+            target("Python.exe", &["python", "aider.py"]),
+            found("aider.py")
+        );
+    }
+
+    #[test]
+    fn inline_code_and_non_interpreters_have_no_script() {
+        assert_eq!(target("node", &["node", "-e", "1"]), None);
+        assert_eq!(target("python", &["python", "-c", "print(1)"]), None);
+        assert_eq!(target("node", &["node"]), None);
+        assert_eq!(target("bash", &["bash", "cli.js"]), None);
+    }
+
+    #[test]
+    fn default_scripts_identify_interpreted_clis() {
+        let registry = CliRegistry::default();
+        let cases = [
+            ("node", vec!["node", CLAUDE_JS], Some("claude")),
+            (
+                "node",
+                vec!["node", "/home/me/.bun/bin/gemini"],
+                Some("gemini"),
+            ),
+            (
+                "python3.12",
+                vec!["python3.12", "-m", "aider"],
+                Some("aider"),
+            ),
+            (
+                "python3",
+                vec!["python3", "-m", "aider.main"],
+                Some("aider"),
+            ),
+            ("node", vec!["node", "-e", "require('@openai/codex')"], None),
+            ("node", vec!["node", "/srv/app/server.js"], None),
+        ];
+        for (name, args, expected) in cases {
+            assert_eq!(matched(&registry, name, &args), expected, "{:?}", args);
+        }
+    }
+}
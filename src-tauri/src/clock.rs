@@ -0,0 +1,93 @@
+//! 可注入的时钟
+//!
+//! 状态机、久坐计时和键鼠活动监听都通过 Clock 取时间和等待，
+//! 运行时使用 SystemClock，测试中使用 ManualClock 手动拨动时间。
+
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 时间来源
+pub trait Clock: Send + Sync {
+    /// 单调时间，用于计算间隔
+    fn now(&self) -> Instant;
+    /// Unix 时间戳（秒），用于跨线程比较活动时间
+    fn unix_secs(&self) -> u64;
+    /// 等待指定时长
+    fn sleep(&self, duration: Duration);
+}
+
+/// 共享时钟
+pub type SharedClock = Arc<dyn Clock>;
+
+/// 系统时钟
+pub struct SystemClock;
+
+impl SystemClock {
+    pub fn shared() -> SharedClock {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix_secs(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// 手动时钟：只有调用 advance 或 sleep 时时间才会前进（测试使用）
+#[cfg(test)]
+pub struct ManualClock {
+    origin: Instant,
+    unix_origin: u64,
+    offset: Mutex<Duration>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new(unix_origin: u64) -> Self {
+        Self {
+            origin: Instant::now(),
+            unix_origin,
+            offset: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// 拨动时间
+    pub fn advance(&self, duration: Duration) {
+        *self.offset.lock().unwrap() += duration;
+    }
+
+    /// 自创建以来经过的时间
+    pub fn elapsed(&self) -> Duration {
+        *self.offset.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+
+    fn unix_secs(&self) -> u64 {
+        self.unix_origin + self.elapsed().as_secs()
+    }
+
+    /// 不真正等待，直接把时间拨过去
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
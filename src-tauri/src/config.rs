@@ -1,8 +1,20 @@
+use crate::channels::{default_channels, ChannelConfig};
+use crate::cli_registry::{default_definitions, CliDefinition, CliRegistry};
+use crate::escalation::{default_ladder, EscalationStep};
 use crate::i18n::Language;
+use crate::machine::{TimingTable, Timings};
+use crate::quiet_hours::{self, QuietReason, QuietWindow};
+use crate::templates::{NotificationTemplate, TemplateKind};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(feature = "tray")]
 use tauri_plugin_store::StoreExt;
 
+/// 与 tauri.conf.json 中的 identifier 一致，决定应用数据目录
+const APP_IDENTIFIER: &str = "com.huglemon.focusguard";
 const CONFIG_FILE: &str = "config.json";
 const KEY_SHOW_TIME: &str = "show_time_in_tray";
 const KEY_POLL_INTERVAL: &str = "poll_interval_secs";
@@ -11,6 +23,46 @@ const KEY_AUTO_BRING_TO_FRONT: &str = "auto_bring_to_front";
 const KEY_SITTING_REMINDER_ENABLED: &str = "sitting_reminder_enabled";
 const KEY_SITTING_REMINDER_INTERVAL: &str = "sitting_reminder_interval_minutes";
 const KEY_LANGUAGE: &str = "language";
+const KEY_STOP_DELAY: &str = "stop_delay_secs";
+const KEY_IDLE_ESCALATION: &str = "idle_escalation_secs";
+const KEY_FALLBACK_SCAN_INTERVAL: &str = "fallback_scan_interval_secs";
+const KEY_STANDUP_CHECK: &str = "standup_check_secs";
+const KEY_CLI_TIMING_OVERRIDES: &str = "cli_timing_overrides";
+const KEY_HISTORY_ENABLED: &str = "history_enabled";
+const KEY_HISTORY_RETENTION_DAYS: &str = "history_retention_days";
+const KEY_AUTO_DAILY_REPORT: &str = "auto_daily_report";
+const KEY_CLI_REGISTRY: &str = "cli_registry";
+const KEY_RESOURCE_SAMPLE_INTERVAL: &str = "resource_sample_interval_secs";
+const KEY_STALL_AFTER: &str = "stall_after_secs";
+const KEY_STALL_NOTIFY: &str = "stall_notify";
+const KEY_OFFLINE_RETENTION: &str = "offline_retention_secs";
+const KEY_NOTIFICATION_CHANNELS: &str = "notification_channels";
+const KEY_WAIT_ESCALATION: &str = "wait_escalation";
+const KEY_QUIET_HOURS: &str = "quiet_hours";
+const KEY_SNOOZE_MINUTES: &str = "snooze_minutes";
+const KEY_SNOOZE_UNTIL: &str = "snooze_until";
+const KEY_MEETING_MODE: &str = "meeting_mode";
+const KEY_NOTIFICATION_TEMPLATES: &str = "notification_templates";
+
+/// 各项时间参数的取值范围（秒）
+const STOP_DELAY_RANGE: (u64, u64) = (1, 60);
+const IDLE_ESCALATION_RANGE: (u64, u64) = (5, 3600);
+const FALLBACK_SCAN_RANGE: (u64, u64) = (2, 300);
+const STANDUP_CHECK_RANGE: (u64, u64) = (30, 900);
+const HISTORY_RETENTION_RANGE: (u64, u64) = (1, 3650);
+const RESOURCE_SAMPLE_RANGE: (u64, u64) = (2, 300);
+const STALL_AFTER_RANGE: (u64, u64) = (60, 7200);
+const OFFLINE_RETENTION_RANGE: (u64, u64) = (10, 86400);
+const SNOOZE_MINUTES_RANGE: (u64, u64) = (5, 480);
+
+/// 单个 CLI 的时间参数覆盖，未设置的项使用全局值
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CliTimingOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_delay_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_escalation_secs: Option<u64>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -21,6 +73,30 @@ pub struct AppConfig {
     pub sitting_reminder_enabled: bool,       // 是否启用智能久坐提醒
     pub sitting_reminder_interval_minutes: u32, // 提醒间隔（分钟）
     pub language: Language,           // 界面语言
+    pub stop_delay_secs: u64,                   // Stop 后多久没有新事件视为等待输入
+    pub idle_escalation_secs: u64,              // 等待输入多久后转为空闲
+    pub fallback_scan_interval_secs: u64,       // 兜底进程检测间隔
+    pub standup_check_secs: u64,                // 久坐提醒后检查是否休息的窗口
+    pub cli_timing_overrides: HashMap<String, CliTimingOverride>, // 按 CLI 覆盖时间参数
+    pub history_enabled: bool,                  // 是否记录会话历史
+    pub history_retention_days: u64,            // 历史记录保留天数
+    pub auto_daily_report: bool,                // 每天结束时自动生成日报（周日同时生成周报）
+    pub cli_registry: Vec<CliDefinition>,       // 受监控的 CLI 列表
+    pub resource_sample_interval_secs: u64,     // 会话 CPU/内存采样间隔
+    pub stall_after_secs: u64,                  // Working 会话多久没有事件和 CPU 占用视为卡住
+    pub stall_notify: bool,                     // 会话卡住时发送通知
+    pub offline_retention_secs: u64,            // 下线的会话保留多久后删除
+    pub notification_channels: Vec<ChannelConfig>, // 通知渠道及各自接收的事件
+    pub wait_escalation: Vec<EscalationStep>,   // 等待输入后的提醒升级阶梯
+    pub quiet_hours: Vec<QuietWindow>,          // 免打扰时段
+    pub snooze_minutes: u64,                    // 托盘"暂停提醒"一次暂停多久
+    pub snooze_until: Option<u64>,              // 暂停提醒到何时（Unix 时间戳）
+    pub meeting_mode: bool,                     // 会议模式，手动关闭前一直免打扰
+    pub notification_templates: HashMap<TemplateKind, NotificationTemplate>, // 自定义通知文案
+    /// 配置文件中出现过的结构化配置项；只有这些项会写回，
+    /// 没有自定义的项不保存，以后内置默认值更新时才能生效
+    #[serde(skip)]
+    pub customized: HashSet<&'static str>,
 }
 
 impl Default for AppConfig {
@@ -33,75 +109,411 @@ impl Default for AppConfig {
             sitting_reminder_enabled: true, // 默认开启
             sitting_reminder_interval_minutes: 40, // 默认40分钟
             language: Language::default(), // 默认英文
+            stop_delay_secs: 3,
+            idle_escalation_secs: 60,
+            fallback_scan_interval_secs: 10,
+            standup_check_secs: 120,
+            cli_timing_overrides: default_cli_timing_overrides(),
+            history_enabled: true,
+            history_retention_days: 30,
+            auto_daily_report: false,
+            cli_registry: default_definitions(),
+            resource_sample_interval_secs: 5,
+            stall_after_secs: 600,
+            stall_notify: true,
+            offline_retention_secs: 300,
+            notification_channels: default_channels(),
+            wait_escalation: default_ladder(),
+            quiet_hours: Vec::new(),
+            snooze_minutes: 30,
+            snooze_until: None,
+            meeting_mode: false,
+            notification_templates: HashMap::new(),
+            customized: HashSet::new(),
         }
     }
 }
 
+/// Gemini 的 AfterTool 每次工具调用后都会触发，间隔比 Claude 的 Stop 密得多，
+/// 默认给更长的 Stop 延迟，避免工具调用之间误报红色
+fn default_cli_timing_overrides() -> HashMap<String, CliTimingOverride> {
+    let mut overrides = HashMap::new();
+    overrides.insert(
+        "gemini".to_string(),
+        CliTimingOverride {
+            stop_delay_secs: Some(8),
+            idle_escalation_secs: None,
+        },
+    );
+    overrides
+}
+
+impl AppConfig {
+    /// 把时间参数限制在合理范围内，返回被修正的项
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut fixes = Vec::new();
+        clamp_secs(
+            KEY_STOP_DELAY,
+            &mut self.stop_delay_secs,
+            STOP_DELAY_RANGE,
+            &mut fixes,
+        );
+        clamp_secs(
+            KEY_IDLE_ESCALATION,
+            &mut self.idle_escalation_secs,
+            IDLE_ESCALATION_RANGE,
+            &mut fixes,
+        );
+        clamp_secs(
+            KEY_FALLBACK_SCAN_INTERVAL,
+            &mut self.fallback_scan_interval_secs,
+            FALLBACK_SCAN_RANGE,
+            &mut fixes,
+        );
+        clamp_secs(
+            KEY_STANDUP_CHECK,
+            &mut self.standup_check_secs,
+            STANDUP_CHECK_RANGE,
+            &mut fixes,
+        );
+        clamp_secs(
+            KEY_HISTORY_RETENTION_DAYS,
+            &mut self.history_retention_days,
+            HISTORY_RETENTION_RANGE,
+            &mut fixes,
+        );
+        clamp_secs(
+            KEY_RESOURCE_SAMPLE_INTERVAL,
+            &mut self.resource_sample_interval_secs,
+            RESOURCE_SAMPLE_RANGE,
+            &mut fixes,
+        );
+        clamp_secs(
+            KEY_STALL_AFTER,
+            &mut self.stall_after_secs,
+            STALL_AFTER_RANGE,
+            &mut fixes,
+        );
+        clamp_secs(
+            KEY_OFFLINE_RETENTION,
+            &mut self.offline_retention_secs,
+            OFFLINE_RETENTION_RANGE,
+            &mut fixes,
+        );
+
+        for (cli, o) in self.cli_timing_overrides.iter_mut() {
+            if let Some(v) = o.stop_delay_secs.as_mut() {
+                clamp_secs(
+                    &format!("{}.{}", cli, KEY_STOP_DELAY),
+                    v,
+                    STOP_DELAY_RANGE,
+                    &mut fixes,
+                );
+            }
+            if let Some(v) = o.idle_escalation_secs.as_mut() {
+                clamp_secs(
+                    &format!("{}.{}", cli, KEY_IDLE_ESCALATION),
+                    v,
+                    IDLE_ESCALATION_RANGE,
+                    &mut fixes,
+                );
+            }
+        }
+
+        clamp_secs(
+            KEY_SNOOZE_MINUTES,
+            &mut self.snooze_minutes,
+            SNOOZE_MINUTES_RANGE,
+            &mut fixes,
+        );
+        self.quiet_hours.retain(|window| match window.times() {
+            Ok(_) => true,
+            Err(e) => {
+                fixes.push(format!("{}: dropped window ({})", KEY_QUIET_HOURS, e));
+                false
+            }
+        });
+
+        // 阶梯按时间顺序执行
+        self.wait_escalation.sort_by_key(|step| step.after_secs);
+        fixes
+    }
+
+    /// 需要写回的结构化配置（只包含 customized 中的项）
+    fn customized_values(&self) -> Vec<(&'static str, serde_json::Value)> {
+        let structured = [
+            (KEY_CLI_REGISTRY, serde_json::to_value(&self.cli_registry)),
+            (
+                KEY_CLI_TIMING_OVERRIDES,
+                serde_json::to_value(&self.cli_timing_overrides),
+            ),
+            (
+                KEY_NOTIFICATION_CHANNELS,
+                serde_json::to_value(&self.notification_channels),
+            ),
+            (
+                KEY_WAIT_ESCALATION,
+                serde_json::to_value(&self.wait_escalation),
+            ),
+        ];
+        structured
+            .into_iter()
+            .filter(|(key, _)| self.customized.contains(key))
+            .filter_map(|(key, value)| Some((key, value.ok()?)))
+            .collect()
+    }
+
+    /// 生成状态机使用的时间参数表
+    pub fn timing_table(&self) -> TimingTable {
+        let default = Timings::from_secs(self.stop_delay_secs, self.idle_escalation_secs);
+        let overrides = self
+            .cli_timing_overrides
+            .iter()
+            .map(|(cli, o)| {
+                let timings = Timings::from_secs(
+                    o.stop_delay_secs.unwrap_or(self.stop_delay_secs),
+                    o.idle_escalation_secs.unwrap_or(self.idle_escalation_secs),
+                );
+                (cli.to_lowercase(), timings)
+            })
+            .collect();
+        TimingTable {
+            default,
+            overrides,
+            stall_after: Some(Duration::from_secs(self.stall_after_secs)),
+            offline_retention: Some(Duration::from_secs(self.offline_retention_secs)),
+        }
+    }
+}
+
+fn clamp_secs(name: &str, value: &mut u64, (min, max): (u64, u64), fixes: &mut Vec<String>) {
+    let clamped = (*value).clamp(min, max);
+    if clamped != *value {
+        fixes.push(format!(
+            "{} = {} out of range {}..={}, using {}",
+            name, value, min, max, clamped
+        ));
+        *value = clamped;
+    }
+}
+
 pub struct ConfigManager {
     config: Arc<Mutex<AppConfig>>,
+    /// 由 cli_registry 编译而来，配置加载后更新
+    registry: Mutex<Arc<CliRegistry>>,
 }
 
 impl ConfigManager {
     pub fn new() -> Self {
         Self {
             config: Arc::new(Mutex::new(AppConfig::default())),
+            registry: Mutex::new(Arc::new(CliRegistry::default())),
         }
     }
 
+    #[cfg(feature = "tray")]
     pub fn load(&self, app: &tauri::AppHandle) {
         if let Ok(store) = app.store(CONFIG_FILE) {
-            let mut config = self.config.lock().unwrap();
+            self.apply(|key| store.get(key));
+        }
+    }
 
-            if let Some(value) = store.get(KEY_SHOW_TIME) {
-                if let Some(v) = value.as_bool() {
-                    config.show_time_in_tray = v;
-                }
+    /// 不依赖 Tauri，直接读取 store 保存的配置文件（无界面模式使用）
+    pub fn load_from_file(&self) {
+        let path = config_file_path();
+        let data = match std::fs::read_to_string(&path) {
+            Ok(d) => d,
+            Err(_) => {
+                println!("Config file not found, using defaults: {}", path.display());
+                return;
             }
-            if let Some(value) = store.get(KEY_POLL_INTERVAL) {
-                if let Some(v) = value.as_u64() {
-                    config.poll_interval_secs = v.max(1); // 最小1秒
-                }
+        };
+        match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&data) {
+            Ok(values) => self.apply(|key| values.get(key).cloned()),
+            Err(e) => eprintln!("Failed to parse config file {}: {}", path.display(), e),
+        }
+    }
+
+    /// 从键值来源读取各项配置
+    fn apply(&self, get: impl Fn(&str) -> Option<serde_json::Value>) {
+        let mut config = self.config.lock().unwrap();
+
+        if let Some(value) = get(KEY_SHOW_TIME) {
+            if let Some(v) = value.as_bool() {
+                config.show_time_in_tray = v;
             }
-            if let Some(value) = store.get(KEY_SOUND_ENABLED) {
-                if let Some(v) = value.as_bool() {
-                    config.sound_enabled = v;
-                }
+        }
+        if let Some(value) = get(KEY_POLL_INTERVAL) {
+            if let Some(v) = value.as_u64() {
+                config.poll_interval_secs = v.max(1); // 最小1秒
+            }
+        }
+        if let Some(value) = get(KEY_SOUND_ENABLED) {
+            if let Some(v) = value.as_bool() {
+                config.sound_enabled = v;
+            }
+        }
+        if let Some(value) = get(KEY_AUTO_BRING_TO_FRONT) {
+            if let Some(v) = value.as_bool() {
+                config.auto_bring_to_front = v;
+            }
+        }
+        if let Some(value) = get(KEY_SITTING_REMINDER_ENABLED) {
+            if let Some(v) = value.as_bool() {
+                config.sitting_reminder_enabled = v;
             }
-            if let Some(value) = store.get(KEY_AUTO_BRING_TO_FRONT) {
-                if let Some(v) = value.as_bool() {
-                    config.auto_bring_to_front = v;
+        }
+        if let Some(value) = get(KEY_SITTING_REMINDER_INTERVAL) {
+            if let Some(v) = value.as_u64() {
+                config.sitting_reminder_interval_minutes = v.max(1) as u32;
+            }
+        }
+        if let Some(value) = get(KEY_LANGUAGE) {
+            if let Some(v) = value.as_str() {
+                config.language = match v {
+                    "Chinese" => Language::Chinese,
+                    _ => Language::English,
+                };
+            }
+        }
+        if let Some(value) = get(KEY_STOP_DELAY) {
+            if let Some(v) = value.as_u64() {
+                config.stop_delay_secs = v;
+            }
+        }
+        if let Some(value) = get(KEY_IDLE_ESCALATION) {
+            if let Some(v) = value.as_u64() {
+                config.idle_escalation_secs = v;
+            }
+        }
+        if let Some(value) = get(KEY_FALLBACK_SCAN_INTERVAL) {
+            if let Some(v) = value.as_u64() {
+                config.fallback_scan_interval_secs = v;
+            }
+        }
+        if let Some(value) = get(KEY_STANDUP_CHECK) {
+            if let Some(v) = value.as_u64() {
+                config.standup_check_secs = v;
+            }
+        }
+        if let Some(value) = get(KEY_HISTORY_ENABLED) {
+            if let Some(v) = value.as_bool() {
+                config.history_enabled = v;
+            }
+        }
+        if let Some(value) = get(KEY_HISTORY_RETENTION_DAYS) {
+            if let Some(v) = value.as_u64() {
+                config.history_retention_days = v;
+            }
+        }
+        if let Some(value) = get(KEY_AUTO_DAILY_REPORT) {
+            if let Some(v) = value.as_bool() {
+                config.auto_daily_report = v;
+            }
+        }
+        if let Some(value) = get(KEY_RESOURCE_SAMPLE_INTERVAL) {
+            if let Some(v) = value.as_u64() {
+                config.resource_sample_interval_secs = v;
+            }
+        }
+        if let Some(value) = get(KEY_STALL_AFTER) {
+            if let Some(v) = value.as_u64() {
+                config.stall_after_secs = v;
+            }
+        }
+        if let Some(value) = get(KEY_STALL_NOTIFY) {
+            if let Some(v) = value.as_bool() {
+                config.stall_notify = v;
+            }
+        }
+        if let Some(value) = get(KEY_OFFLINE_RETENTION) {
+            if let Some(v) = value.as_u64() {
+                config.offline_retention_secs = v;
+            }
+        }
+        if let Some(value) = get(KEY_CLI_REGISTRY) {
+            match serde_json::from_value::<Vec<CliDefinition>>(value) {
+                Ok(v) => {
+                    config.cli_registry = v;
+                    config.customized.insert(KEY_CLI_REGISTRY);
                 }
+                Err(e) => eprintln!("Invalid {}: {}", KEY_CLI_REGISTRY, e),
             }
-            if let Some(value) = store.get(KEY_SITTING_REMINDER_ENABLED) {
-                if let Some(v) = value.as_bool() {
-                    config.sitting_reminder_enabled = v;
+        }
+        if let Some(value) = get(KEY_NOTIFICATION_CHANNELS) {
+            match serde_json::from_value::<Vec<ChannelConfig>>(value) {
+                Ok(v) => {
+                    config.notification_channels = v;
+                    config.customized.insert(KEY_NOTIFICATION_CHANNELS);
                 }
+                Err(e) => eprintln!("Invalid {}: {}", KEY_NOTIFICATION_CHANNELS, e),
             }
-            if let Some(value) = store.get(KEY_SITTING_REMINDER_INTERVAL) {
-                if let Some(v) = value.as_u64() {
-                    config.sitting_reminder_interval_minutes = v.max(1) as u32;
+        }
+        if let Some(value) = get(KEY_WAIT_ESCALATION) {
+            match serde_json::from_value::<Vec<EscalationStep>>(value) {
+                Ok(v) => {
+                    config.wait_escalation = v;
+                    config.customized.insert(KEY_WAIT_ESCALATION);
                 }
+                Err(e) => eprintln!("Invalid {}: {}", KEY_WAIT_ESCALATION, e),
+            }
+        }
+        if let Some(value) = get(KEY_QUIET_HOURS) {
+            match serde_json::from_value::<Vec<QuietWindow>>(value) {
+                Ok(v) => config.quiet_hours = v,
+                Err(e) => eprintln!("Invalid {}: {}", KEY_QUIET_HOURS, e),
+            }
+        }
+        if let Some(value) = get(KEY_SNOOZE_MINUTES) {
+            if let Some(v) = value.as_u64() {
+                config.snooze_minutes = v;
+            }
+        }
+        if let Some(value) = get(KEY_SNOOZE_UNTIL) {
+            config.snooze_until = value.as_u64();
+        }
+        if let Some(value) = get(KEY_MEETING_MODE) {
+            if let Some(v) = value.as_bool() {
+                config.meeting_mode = v;
+            }
+        }
+        if let Some(value) = get(KEY_NOTIFICATION_TEMPLATES) {
+            match serde_json::from_value::<HashMap<TemplateKind, NotificationTemplate>>(value) {
+                Ok(v) => config.notification_templates = v,
+                Err(e) => eprintln!("Invalid {}: {}", KEY_NOTIFICATION_TEMPLATES, e),
             }
-            if let Some(value) = store.get(KEY_LANGUAGE) {
-                if let Some(v) = value.as_str() {
-                    config.language = match v {
-                        "Chinese" => Language::Chinese,
-                        _ => Language::English,
-                    };
+        }
+        if let Some(value) = get(KEY_CLI_TIMING_OVERRIDES) {
+            match serde_json::from_value::<HashMap<String, CliTimingOverride>>(value) {
+                Ok(v) => {
+                    config.cli_timing_overrides = v;
+                    config.customized.insert(KEY_CLI_TIMING_OVERRIDES);
                 }
+                Err(e) => eprintln!("Invalid {}: {}", KEY_CLI_TIMING_OVERRIDES, e),
             }
         }
+
+        for fix in config.validate() {
+            eprintln!("Config: {}", fix);
+        }
+
+        let (registry, errors) = CliRegistry::compile(&config.cli_registry);
+        for error in errors {
+            eprintln!("Config: {}", error);
+        }
+        *self.registry.lock().unwrap() = Arc::new(registry);
     }
 
+    #[cfg(feature = "tray")]
     pub fn save(&self, app: &tauri::AppHandle) {
         if let Ok(store) = app.store(CONFIG_FILE) {
             let config = self.config.lock().unwrap();
-            let _ = store.set(KEY_SHOW_TIME, config.show_time_in_tray);
-            let _ = store.set(KEY_POLL_INTERVAL, config.poll_interval_secs);
-            let _ = store.set(KEY_SOUND_ENABLED, config.sound_enabled);
-            let _ = store.set(KEY_AUTO_BRING_TO_FRONT, config.auto_bring_to_front);
-            let _ = store.set(KEY_SITTING_REMINDER_ENABLED, config.sitting_reminder_enabled);
-            let _ = store.set(
+            store.set(KEY_SHOW_TIME, config.show_time_in_tray);
+            store.set(KEY_POLL_INTERVAL, config.poll_interval_secs);
+            store.set(KEY_SOUND_ENABLED, config.sound_enabled);
+            store.set(KEY_AUTO_BRING_TO_FRONT, config.auto_bring_to_front);
+            store.set(KEY_SITTING_REMINDER_ENABLED, config.sitting_reminder_enabled);
+            store.set(
                 KEY_SITTING_REMINDER_INTERVAL,
                 config.sitting_reminder_interval_minutes,
             );
@@ -109,7 +521,36 @@ impl ConfigManager {
                 Language::English => "English",
                 Language::Chinese => "Chinese",
             };
-            let _ = store.set(KEY_LANGUAGE, lang_str);
+            store.set(KEY_LANGUAGE, lang_str);
+            store.set(KEY_STOP_DELAY, config.stop_delay_secs);
+            store.set(KEY_IDLE_ESCALATION, config.idle_escalation_secs);
+            store.set(
+                KEY_FALLBACK_SCAN_INTERVAL,
+                config.fallback_scan_interval_secs,
+            );
+            store.set(KEY_STANDUP_CHECK, config.standup_check_secs);
+            store.set(KEY_HISTORY_ENABLED, config.history_enabled);
+            store.set(KEY_HISTORY_RETENTION_DAYS, config.history_retention_days);
+            store.set(KEY_AUTO_DAILY_REPORT, config.auto_daily_report);
+            store.set(
+                KEY_RESOURCE_SAMPLE_INTERVAL,
+                config.resource_sample_interval_secs,
+            );
+            store.set(KEY_STALL_AFTER, config.stall_after_secs);
+            store.set(KEY_STALL_NOTIFY, config.stall_notify);
+            store.set(KEY_OFFLINE_RETENTION, config.offline_retention_secs);
+            for (key, value) in config.customized_values() {
+                store.set(key, value);
+            }
+            if let Ok(windows) = serde_json::to_value(&config.quiet_hours) {
+                store.set(KEY_QUIET_HOURS, windows);
+            }
+            store.set(KEY_SNOOZE_MINUTES, config.snooze_minutes);
+            store.set(KEY_SNOOZE_UNTIL, config.snooze_until);
+            store.set(KEY_MEETING_MODE, config.meeting_mode);
+            if let Ok(templates) = serde_json::to_value(&config.notification_templates) {
+                store.set(KEY_NOTIFICATION_TEMPLATES, templates);
+            }
             let _ = store.save();
         }
     }
@@ -195,6 +636,141 @@ impl ConfigManager {
         config.language = config.language.toggle();
         config.language
     }
+
+    /// 状态管理线程启动时读取一次，时间参数只能在配置文件中修改，重启后生效
+    pub fn get_timing_table(&self) -> TimingTable {
+        self.config.lock().unwrap().timing_table()
+    }
+
+    pub fn get_fallback_scan_interval(&self) -> u64 {
+        self.config.lock().unwrap().fallback_scan_interval_secs
+    }
+
+    pub fn get_resource_sample_interval(&self) -> u64 {
+        self.config.lock().unwrap().resource_sample_interval_secs
+    }
+
+    pub fn get_standup_check_secs(&self) -> u64 {
+        self.config.lock().unwrap().standup_check_secs
+    }
+
+    pub fn get_history_enabled(&self) -> bool {
+        self.config.lock().unwrap().history_enabled
+    }
+
+    pub fn get_history_retention_days(&self) -> u64 {
+        self.config.lock().unwrap().history_retention_days
+    }
+
+    pub fn get_cli_registry(&self) -> Arc<CliRegistry> {
+        self.registry.lock().unwrap().clone()
+    }
+
+    pub fn get_auto_daily_report(&self) -> bool {
+        self.config.lock().unwrap().auto_daily_report
+    }
+
+    pub fn toggle_auto_daily_report(&self) -> bool {
+        let mut config = self.config.lock().unwrap();
+        config.auto_daily_report = !config.auto_daily_report;
+        config.auto_daily_report
+    }
+
+    pub fn get_stall_notify(&self) -> bool {
+        self.config.lock().unwrap().stall_notify
+    }
+
+    pub fn toggle_stall_notify(&self) -> bool {
+        let mut config = self.config.lock().unwrap();
+        config.stall_notify = !config.stall_notify;
+        config.stall_notify
+    }
+
+    pub fn get_notification_channels(&self) -> Vec<ChannelConfig> {
+        self.config.lock().unwrap().notification_channels.clone()
+    }
+
+    pub fn get_wait_escalation(&self) -> Vec<EscalationStep> {
+        self.config.lock().unwrap().wait_escalation.clone()
+    }
+
+    pub fn get_snooze_minutes(&self) -> u64 {
+        self.config.lock().unwrap().snooze_minutes
+    }
+
+    /// 暂停提醒的截止时间，未暂停或已过期时返回 None
+    pub fn get_snooze_until(&self, now: u64) -> Option<u64> {
+        self.config
+            .lock()
+            .unwrap()
+            .snooze_until
+            .filter(|&until| until > now)
+    }
+
+    /// 从 now 开始暂停提醒 snooze_minutes 分钟，返回截止时间
+    pub fn snooze(&self, now: u64) -> u64 {
+        let mut config = self.config.lock().unwrap();
+        let until = now + config.snooze_minutes * 60;
+        config.snooze_until = Some(until);
+        until
+    }
+
+    pub fn cancel_snooze(&self) {
+        self.config.lock().unwrap().snooze_until = None;
+    }
+
+    pub fn get_meeting_mode(&self) -> bool {
+        self.config.lock().unwrap().meeting_mode
+    }
+
+    pub fn toggle_meeting_mode(&self) -> bool {
+        let mut config = self.config.lock().unwrap();
+        config.meeting_mode = !config.meeting_mode;
+        config.meeting_mode
+    }
+
+    pub fn get_notification_template(&self, kind: TemplateKind) -> Option<NotificationTemplate> {
+        self.config
+            .lock()
+            .unwrap()
+            .notification_templates
+            .get(&kind)
+            .cloned()
+    }
+
+    /// now 时刻是否处于免打扰，返回原因
+    pub fn quiet_reason(&self, now: u64) -> Option<QuietReason> {
+        let config = self.config.lock().unwrap();
+        if config.meeting_mode {
+            Some(QuietReason::Meeting)
+        } else if config.snooze_until.is_some_and(|until| until > now) {
+            Some(QuietReason::Snooze)
+        } else if quiet_hours::in_schedule(&config.quiet_hours, quiet_hours::local_datetime(now)) {
+            Some(QuietReason::Schedule)
+        } else {
+            None
+        }
+    }
+}
+
+/// 应用数据目录（与 Tauri 的 app_data_dir 规则一致）
+pub fn app_data_dir() -> PathBuf {
+    let home = std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let base = if cfg!(target_os = "macos") {
+        home.join("Library").join("Application Support")
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".local").join("share"))
+    };
+    base.join(APP_IDENTIFIER)
+}
+
+fn config_file_path() -> PathBuf {
+    app_data_dir().join(CONFIG_FILE)
 }
 
 impl Default for ConfigManager {
@@ -202,3 +778,116 @@ impl Default for ConfigManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::escalation::EscalationAction;
+    use serde_json::json;
+
+    /// 用 JSON 对象模拟配置文件加载
+    fn load(values: serde_json::Value) -> AppConfig {
+        let manager = ConfigManager::new();
+        let values = values.as_object().unwrap().clone();
+        manager.apply(|key| values.get(key).cloned());
+        let config = manager.config.lock().unwrap();
+        config.clone()
+    }
+
+    fn step(after_secs: u64, action: EscalationAction) -> EscalationStep {
+        EscalationStep { after_secs, action }
+    }
+
+    #[test]
+    fn defaults_need_no_fixes() {
+        assert!(AppConfig::default().validate().is_empty());
+    }
+
+    #[test]
+    fn validate_clamps_timings_and_overrides() {
+        let mut config = AppConfig {
+            stop_delay_secs: 0,
+            idle_escalation_secs: 100_000,
+            standup_check_secs: 10,
+            ..AppConfig::default()
+        };
+        config.cli_timing_overrides.insert(
+            "codex".to_string(),
+            CliTimingOverride {
+                stop_delay_secs: Some(500),
+                idle_escalation_secs: Some(60),
+            },
+        );
+
+        let fixes = config.validate();
+        assert_eq!(config.stop_delay_secs, 1);
+        assert_eq!(config.idle_escalation_secs, 3600);
+        assert_eq!(config.standup_check_secs, 30);
+        let codex = &config.cli_timing_overrides["codex"];
+        assert_eq!(codex.stop_delay_secs, Some(60));
+        assert_eq!(codex.idle_escalation_secs, Some(60));
+        assert_eq!(fixes.len(), 4);
+        let fix = "codex.stop_delay_secs = 500 out of range 1..=60, using 60";
+        assert!(fixes.iter().any(|f| f == fix));
+    }
+
+    #[test]
+    fn validate_drops_bad_quiet_windows_and_sorts_the_ladder() {
+        let mut config = AppConfig {
+            quiet_hours: vec![
+                QuietWindow {
+                    days: Vec::new(),
+                    start: "22:00".to_string(),
+                    end: "08:00".to_string(),
+                },
+                QuietWindow {
+                    days: Vec::new(),
+                    start: "25:00".to_string(),
+                    end: "08:00".to_string(),
+                },
+            ],
+            wait_escalation: vec![
+                step(300, EscalationAction::Push),
+                step(0, EscalationAction::Notify),
+                step(60, EscalationAction::Sound),
+            ],
+            ..AppConfig::default()
+        };
+
+        let fixes = config.validate();
+        assert_eq!(config.quiet_hours.len(), 1);
+        assert_eq!(config.quiet_hours[0].start, "22:00");
+        assert_eq!(fixes.len(), 1);
+        assert!(fixes[0].starts_with("quiet_hours: dropped window"));
+        assert_eq!(
+            config.wait_escalation,
+            [
+                step(0, EscalationAction::Notify),
+                step(60, EscalationAction::Sound),
+                step(300, EscalationAction::Push),
+            ]
+        );
+    }
+
+    #[test]
+    fn loaded_values_are_validated() {
+        let config = load(json!({ "stop_delay_secs": 999, "snooze_minutes": 1 }));
+        assert_eq!(config.stop_delay_secs, 60);
+        assert_eq!(config.snooze_minutes, 5);
+    }
+
+    #[test]
+    fn only_customized_structured_settings_are_saved() {
+        assert!(load(json!({})).customized_values().is_empty());
+
+        let ladder = json!([{ "after_secs": 0, "action": "notify" }]);
+        let config = load(json!({
+            "wait_escalation": ladder,
+            // 无法解析的项保持默认值，也不会写回
+            "notification_channels": "desktop",
+        }));
+        let saved = config.customized_values();
+        assert_eq!(saved, [(KEY_WAIT_ESCALATION, ladder)]);
+        assert_eq!(config.notification_channels, default_channels());
+    }
+}
@@ -0,0 +1,970 @@
+//! 与界面无关的后台引擎：IPC 服务器、状态管理、兜底进程检测、久坐计时
+//!
+//! 托盘模式和无界面（--headless）模式共用这套逻辑，
+//! 界面相关的部分通过 Frontend trait 注入。
+
+use crate::activity_monitor::{self, ActivityMonitor};
+use crate::analytics::{self, LatencyReport};
+use crate::channels::{self, ChannelConfig, Notification, NotificationChannel, NotifyEvent};
+use crate::cli_registry::CliRegistry;
+use crate::clock::{SharedClock, SystemClock};
+use crate::config::ConfigManager;
+use crate::escalation::{EscalationAction, Escalator};
+use crate::history::{HistoryRecord, HistoryStore, ResetReason};
+use crate::i18n::get_strings;
+use crate::ipc_server::{
+    self, CliEvent, CliMessage, IpcContext, IpcEvent, ProtocolStats, ReminderAction, Subscribers,
+};
+use crate::machine::Effect;
+use crate::notification;
+use crate::process_monitor;
+use crate::process_monitor::{PidCache, ProcessInfo};
+use crate::quiet_hours::{self, QuietQueue};
+use crate::report::{self, ReportPeriod};
+use crate::resource_monitor::{self, ResourceSampler};
+use crate::state_manager::{
+    aggregate_state, make_state_key, CliState, CliStatus, StateChangeEvent, StateManager,
+    FALLBACK_SESSION_PREFIX,
+};
+use crate::templates::{self, TemplateKind, TemplateVars};
+use crate::window_manager;
+use chrono::{Datelike, Days, Weekday};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 等待提醒阶梯的检查间隔
+const ESCALATION_TICK: Duration = Duration::from_secs(1);
+
+/// 免打扰状态的检查间隔
+const QUIET_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 久坐提醒"稍后提醒"的时长
+const REMINDER_SNOOZE: Duration = Duration::from_secs(10 * 60);
+
+/// 智能久坐提醒状态
+#[derive(Default)]
+pub struct SittingReminderState {
+    pub awaiting_standup: bool,            // 是否等待用户站起来
+    pub reminder_sent_at: Option<Instant>, // 发送提醒的时间
+    pub snoozed_until: Option<Instant>,    // "稍后提醒"到期时间
+    pub skipped_at: Option<u32>,           // 跳过提醒时已坐的分钟数
+}
+
+/// 引擎的输出端（托盘界面或无界面模式）
+pub trait Frontend: Send + Sync + 'static {
+    /// 当前模式下的桌面通知渠道（配置中 type 为 desktop 的渠道使用它）
+    fn desktop_channel(&self) -> Arc<dyn NotificationChannel>;
+    /// 每次状态管理器回调后调用（用于刷新界面）
+    fn state_changed(&self, _event: &StateChangeEvent) {}
+    /// 久坐计时每分钟回调
+    fn sitting_tick(&self, _minutes: u32) {}
+    /// 久坐计时被重置
+    fn timer_reset(&self) {}
+    /// 久坐提醒得到了回应（稍后提醒、已休息或跳过）
+    fn reminder_resolved(&self) {}
+    /// 在线会话的资源占用刷新了
+    fn resources_sampled(&self) {}
+    /// 进入或离开免打扰（用于刷新菜单中的暂停状态）
+    fn quiet_changed(&self) {}
+}
+
+/// 后台引擎共享的状态
+#[derive(Clone)]
+pub struct Engine {
+    pub config: Arc<ConfigManager>,
+    pub clock: SharedClock,
+    pub cli_states: Arc<Mutex<HashMap<String, CliStatus>>>,
+    pub sitting_minutes: Arc<Mutex<u32>>,
+    pub sitting_reminder: Arc<Mutex<SittingReminderState>>,
+    pub activity_monitor: Arc<ActivityMonitor>,
+    pub subscribers: Arc<Subscribers>,
+    pub protocol_stats: Arc<ProtocolStats>,
+    pub history: Arc<HistoryStore>,
+    pub quiet_queue: Arc<Mutex<QuietQueue>>,
+    ipc_sender: Sender<CliMessage>,
+    ipc_receiver: Arc<Mutex<Option<Receiver<CliMessage>>>>,
+}
+
+impl Engine {
+    pub fn new(config: Arc<ConfigManager>) -> Self {
+        Self::with_clock(config, SystemClock::shared())
+    }
+
+    /// 使用指定时钟创建（状态机、久坐计时和键鼠监听共用）
+    pub fn with_clock(config: Arc<ConfigManager>, clock: SharedClock) -> Self {
+        let (ipc_sender, ipc_receiver) = mpsc::channel();
+        Self {
+            config,
+            clock: clock.clone(),
+            cli_states: Arc::new(Mutex::new(HashMap::new())),
+            sitting_minutes: Arc::new(Mutex::new(0)),
+            sitting_reminder: Arc::new(Mutex::new(SittingReminderState::default())),
+            activity_monitor: Arc::new(ActivityMonitor::with_clock(clock)),
+            subscribers: Arc::new(Subscribers::new()),
+            protocol_stats: Arc::new(ProtocolStats::new()),
+            history: Arc::new(HistoryStore::new(HistoryStore::default_path())),
+            quiet_queue: Arc::new(Mutex::new(QuietQueue::default())),
+            ipc_sender,
+            ipc_receiver: Arc::new(Mutex::new(Some(ipc_receiver))),
+        }
+    }
+
+    /// 初始检测 CLI 进程并加入状态列表（每个 PID 一个会话），返回聚合状态
+    pub fn scan_initial_processes(&self) -> CliState {
+        let initial_processes = process_monitor::get_cli_processes(&self.config.get_cli_registry());
+        let mut cli_states = self.cli_states.lock().unwrap();
+        for process in &initial_processes {
+            let cli = process.cli_id.clone();
+            let session_id = fallback_session_id(process.pid);
+            let key = make_state_key(&cli, Some(&session_id));
+            let mut status = CliStatus::with_details(cli, Some(session_id), process.cwd.clone());
+            // 设置为 Working 状态（因为进程正在运行）
+            status.state = CliState::Working;
+            status.pid = Some(process.pid);
+            cli_states.insert(key, status);
+        }
+        aggregate_state(&cli_states)
+    }
+
+    /// 重置久坐计时并通知订阅者
+    pub fn reset_timer(&self) {
+        let previous = std::mem::replace(&mut *self.sitting_minutes.lock().unwrap(), 0);
+        self.record_history(HistoryRecord::SittingReset {
+            ts: self.clock.unix_secs(),
+            minutes: previous,
+            reason: ResetReason::Manual,
+        });
+        self.subscribers
+            .broadcast(&IpcEvent::SittingTick { minutes: 0 });
+    }
+
+    /// 立即删除已下线的会话，返回删除的数量
+    /// 进程已不在的会话通过状态机结束（记录历史、更新聚合状态并通知前端），
+    /// 之后和其他下线会话一样由状态机按保留时间删除，不计入返回值
+    pub fn clear_stale_sessions(&self) -> usize {
+        let registry = self.config.get_cli_registry();
+        let processes = process_monitor::get_cli_processes(&registry);
+
+        let mut states = self.cli_states.lock().unwrap();
+        let ended: Vec<CliMessage> = ended_sessions(&processes, &states)
+            .into_iter()
+            .map(|key| session_end_message(&states[key]))
+            .collect();
+        // 已经下线的会话不影响聚合状态，直接删除
+        let before = states.len();
+        states.retain(|_, status| status.state != CliState::Offline);
+        let removed = before - states.len();
+        drop(states);
+
+        for msg in ended {
+            let _ = self.ipc_sender.send(msg);
+        }
+        removed
+    }
+
+    /// 最近 days 天（含今天）的响应延迟统计
+    pub fn latency_report(&self, days: u32) -> LatencyReport {
+        analytics::recent_report(&self.history, self.clock.unix_secs(), days)
+    }
+
+    /// 生成包含 date 的日报或周报，返回写入的文件（HTML 在前）
+    pub fn write_report(
+        &self,
+        period: ReportPeriod,
+        date: chrono::NaiveDate,
+    ) -> Result<Vec<PathBuf>, String> {
+        let report = report::generate(
+            &self.history,
+            period,
+            date,
+            self.clock.unix_secs(),
+            *self.sitting_minutes.lock().unwrap(),
+        );
+        let paths =
+            report::write_report(&report, self.config.get_language(), &report::reports_dir())?;
+        println!("Report written: {}", paths[0].display());
+        Ok(paths)
+    }
+
+    /// 今天的本地日期
+    pub fn today(&self) -> chrono::NaiveDate {
+        analytics::local_date(self.clock.unix_secs())
+    }
+
+    /// 写入一条历史记录（未开启历史记录时忽略）
+    pub fn record_history(&self, record: HistoryRecord) {
+        if !self.config.get_history_enabled() {
+            return;
+        }
+        if let Err(e) = self.history.append(&record) {
+            eprintln!("History: {}", e);
+        }
+    }
+
+    /// 启动所有后台线程，只能调用一次
+    pub fn start(&self, frontend: Arc<dyn Frontend>) {
+        let receiver = match self.ipc_receiver.lock().unwrap().take() {
+            Some(r) => r,
+            None => {
+                eprintln!("Engine already started");
+                return;
+            }
+        };
+
+        self.spawn_ipc_server(frontend.clone());
+        self.start_state_manager(receiver, frontend.clone());
+        self.spawn_fallback_scanner();
+        self.spawn_resource_sampler(frontend.clone());
+        self.spawn_wait_escalation(frontend.clone());
+        self.spawn_quiet_watch(frontend.clone());
+        self.spawn_sitting_timer(frontend);
+        self.spawn_history_pruner();
+        self.spawn_daily_report();
+    }
+
+    fn spawn_ipc_server(&self, frontend: Arc<dyn Frontend>) {
+        let engine = self.clone();
+        let reminder_engine = self.clone();
+        let reminder_frontend = frontend.clone();
+        let context = IpcContext {
+            cli_states: self.cli_states.clone(),
+            sitting_minutes: self.sitting_minutes.clone(),
+            subscribers: self.subscribers.clone(),
+            protocol_stats: self.protocol_stats.clone(),
+            clock: self.clock.clone(),
+            history: self.history.clone(),
+            on_timer_reset: Arc::new(move |previous| {
+                engine.record_history(HistoryRecord::SittingReset {
+                    ts: engine.clock.unix_secs(),
+                    minutes: previous,
+                    reason: ResetReason::Manual,
+                });
+                engine
+                    .subscribers
+                    .broadcast(&IpcEvent::SittingTick { minutes: 0 });
+                frontend.timer_reset();
+            }),
+            on_reminder_action: Arc::new(move |action| {
+                reminder_engine.reminder_action(action)?;
+                reminder_frontend.reminder_resolved();
+                Ok(())
+            }),
+            resolve_pid: {
+                let config = self.config.clone();
+                let cache = PidCache::default();
+                Arc::new(move |cli, pid| cache.resolve(pid, cli, &config.get_cli_registry()))
+            },
+        };
+        let sender = self.ipc_sender.clone();
+        std::thread::spawn(move || {
+            ipc_server::start_ipc_server(sender, context);
+        });
+    }
+
+    fn start_state_manager(&self, receiver: Receiver<CliMessage>, frontend: Arc<dyn Frontend>) {
+        let engine = self.clone();
+
+        StateManager::with_states(self.cli_states.clone(), self.clock.clone())
+            .with_timings(self.config.get_timing_table())
+            .start(receiver, move |effect: Effect| {
+                let event = match effect {
+                    Effect::Notify(event) => event,
+                    Effect::Transition {
+                        key,
+                        cli,
+                        session_id,
+                        cwd,
+                        from,
+                        to,
+                    } => {
+                        println!("Session {}: {:?} -> {:?}", key, from, to);
+                        let stalled = if to == CliState::Stalled && engine.config.get_stall_notify()
+                        {
+                            engine.cli_states.lock().unwrap().get(&key).cloned()
+                        } else {
+                            None
+                        };
+                        if let Some(status) = stalled {
+                            let lang = engine.config.get_language();
+                            let s = get_strings(lang);
+                            let quiet_for = engine
+                                .clock
+                                .now()
+                                .saturating_duration_since(status.last_update);
+                            let vars = TemplateVars::session(
+                                lang,
+                                &status,
+                                &engine.config.get_cli_registry(),
+                                quiet_for,
+                            );
+                            engine.notify(
+                                frontend.desktop_channel(),
+                                engine.templated(
+                                    TemplateKind::CliStalled,
+                                    NotifyEvent::CliStalled,
+                                    (s.cli_stalled_title, s.cli_stalled_body),
+                                    &vars,
+                                    false,
+                                ),
+                            );
+                        }
+                        engine.record_history(HistoryRecord::Transition {
+                            ts: engine.clock.unix_secs(),
+                            cli,
+                            session_id,
+                            cwd,
+                            from,
+                            to,
+                        });
+                        return;
+                    }
+                };
+
+                // 推送给 IPC 订阅者
+                engine
+                    .subscribers
+                    .broadcast(&IpcEvent::StateChange(event.clone()));
+
+                // 智能久坐提醒：在 CLI Working 事件时检查是否需要提醒
+                if event.state == CliState::Working {
+                    if let Some(minutes) = engine.check_sitting_reminder() {
+                        engine.send_sitting_reminder(&frontend, minutes);
+                    }
+                }
+
+                frontend.state_changed(&event);
+            });
+    }
+
+    /// 按配置的渠道发送通知
+    pub fn notify(&self, desktop: Arc<dyn NotificationChannel>, notification: Notification) {
+        if self.hold_if_quiet(&notification) {
+            return;
+        }
+        channels::dispatch(
+            &self.config.get_notification_channels(),
+            desktop,
+            notification,
+        );
+    }
+
+    /// 按用户配置的模板生成通知，没有配置时使用内置的 (标题, 正文)
+    pub fn templated(
+        &self,
+        kind: TemplateKind,
+        event: NotifyEvent,
+        (default_title, default_body): (&str, &str),
+        vars: &TemplateVars,
+        with_sound: bool,
+    ) -> Notification {
+        let template = self.config.get_notification_template(kind);
+        let (title, body) = templates::render(template.as_ref(), default_title, default_body, vars);
+        Notification::new(event, &title, &body, with_sound)
+    }
+
+    /// 当前是否处于免打扰（会议模式、暂停提醒或静默时段）
+    pub fn is_quiet(&self) -> bool {
+        self.config.quiet_reason(self.clock.unix_secs()).is_some()
+    }
+
+    /// 免打扰期间暂缓 CLI 等待和久坐提醒，结束时汇总发送
+    fn hold_if_quiet(&self, notification: &Notification) -> bool {
+        if !quiet_hours::is_suppressible(notification.event) || !self.is_quiet() {
+            return false;
+        }
+        println!(
+            "Quiet: holding {} notification",
+            notification.event.as_str()
+        );
+        self.quiet_queue.lock().unwrap().hold(notification.clone());
+        true
+    }
+
+    /// 只发到桌面渠道（remote = false）或只发到其他渠道（remote = true）
+    fn notify_reach(
+        &self,
+        desktop: Arc<dyn NotificationChannel>,
+        notification: Notification,
+        remote: bool,
+    ) {
+        if self.hold_if_quiet(&notification) {
+            return;
+        }
+        let configs: Vec<ChannelConfig> = self
+            .config
+            .get_notification_channels()
+            .into_iter()
+            .filter(|c| c.is_remote() == remote)
+            .collect();
+        channels::dispatch(&configs, desktop, notification);
+    }
+
+    /// 检查是否需要发送久坐提醒，需要时返回已坐分钟数并开始监控键鼠活动
+    fn check_sitting_reminder(&self) -> Option<u32> {
+        if !self.config.get_sitting_reminder_enabled() {
+            return None;
+        }
+        let mut reminder = self.sitting_reminder.lock().unwrap();
+        if reminder.awaiting_standup {
+            return None;
+        }
+        if reminder
+            .snoozed_until
+            .is_some_and(|until| self.clock.now() < until)
+        {
+            return None;
+        }
+        let minutes = *self.sitting_minutes.lock().unwrap();
+        // 跳过后要再坐满一个间隔；计时重置后 skipped_at 大于当前分钟数，自然失效
+        let base = reminder.skipped_at.filter(|&m| m <= minutes).unwrap_or(0);
+        if minutes < base + self.config.get_sitting_reminder_interval() {
+            return None;
+        }
+        reminder.snoozed_until = None;
+        reminder.skipped_at = None;
+        reminder.awaiting_standup = true;
+        reminder.reminder_sent_at = Some(self.clock.now());
+        self.record_history(HistoryRecord::SittingReminder {
+            ts: self.clock.unix_secs(),
+            minutes,
+        });
+        // 开始监控键鼠活动（按需监控）
+        self.activity_monitor.start_monitoring();
+        Some(minutes)
+    }
+
+    /// 是否有久坐提醒在等待回应
+    pub fn reminder_pending(&self) -> bool {
+        self.sitting_reminder.lock().unwrap().awaiting_standup
+    }
+
+    /// 处理对久坐提醒的回应（通知按钮、托盘菜单或 IPC）
+    /// "已休息"随时可用，稍后提醒和跳过只在有待回应的提醒时可用
+    pub fn reminder_action(&self, action: ReminderAction) -> Result<(), String> {
+        let mut reminder = self.sitting_reminder.lock().unwrap();
+        if action != ReminderAction::TookBreak && !reminder.awaiting_standup {
+            return Err("no sitting reminder pending".to_string());
+        }
+        match action {
+            ReminderAction::Snooze => {
+                reminder.snoozed_until = Some(self.clock.now() + REMINDER_SNOOZE);
+            }
+            ReminderAction::Skip => {
+                reminder.skipped_at = Some(*self.sitting_minutes.lock().unwrap());
+            }
+            ReminderAction::TookBreak => {
+                let previous = std::mem::replace(&mut *self.sitting_minutes.lock().unwrap(), 0);
+                self.record_history(HistoryRecord::SittingReset {
+                    ts: self.clock.unix_secs(),
+                    minutes: previous,
+                    reason: ResetReason::Break,
+                });
+                self.subscribers
+                    .broadcast(&IpcEvent::SittingTick { minutes: 0 });
+            }
+        }
+        println!("[久坐提醒] 用户回应: {:?}", action);
+
+        // 已经得到回应，不再根据键鼠活动判断是否休息
+        reminder.awaiting_standup = false;
+        reminder.reminder_sent_at = None;
+        self.activity_monitor.stop_monitoring();
+        Ok(())
+    }
+
+    /// 发送久坐提醒：桌面上显示带"稍后提醒 / 已休息 / 跳过"按钮的通知，其他渠道照常发送
+    /// 托盘菜单和 IPC 的 reminder 命令提供同样的操作
+    fn send_sitting_reminder(&self, frontend: &Arc<dyn Frontend>, minutes: u32) {
+        let lang = self.config.get_language();
+        let s = get_strings(lang);
+        let reset_minutes = self.config.get_standup_check_secs().div_ceil(60);
+        let notification = self.templated(
+            TemplateKind::SittingReminder,
+            NotifyEvent::SittingReminder,
+            (s.smart_reminder_title, s.smart_reminder_body),
+            &TemplateVars::new()
+                .set("minutes", minutes.to_string())
+                .set("reset_minutes", reset_minutes.to_string()),
+            self.config.get_sound_enabled(),
+        );
+        if self.hold_if_quiet(&notification) {
+            return;
+        }
+        self.notify_reach(frontend.desktop_channel(), notification.clone(), true);
+
+        let on_desktop = self
+            .config
+            .get_notification_channels()
+            .iter()
+            .any(|c| !c.is_remote() && c.accepts(notification.event));
+        if !on_desktop {
+            return;
+        }
+
+        let engine = self.clone();
+        let frontend = frontend.clone();
+        let timeout = Duration::from_secs(self.config.get_standup_check_secs());
+        std::thread::spawn(move || {
+            const ACTIONS: [ReminderAction; 3] = [
+                ReminderAction::Snooze,
+                ReminderAction::TookBreak,
+                ReminderAction::Skip,
+            ];
+            let buttons = [
+                ("snooze", s.reminder_snooze),
+                ("break", s.reminder_took_break),
+                ("skip", s.reminder_skip),
+            ];
+            if notification.with_sound {
+                notification::play_system_sound();
+            }
+            match notification::ask_desktop_action(
+                &notification.title,
+                &notification.body,
+                &buttons,
+                timeout,
+            ) {
+                Ok(Some(index)) => {
+                    if engine.reminder_action(ACTIONS[index]).is_ok() {
+                        frontend.reminder_resolved();
+                    }
+                }
+                // 没有点击按钮时仍按键鼠活动判断是否休息
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!(
+                        "Actionable notification failed ({}), sending a plain one",
+                        e
+                    );
+                    let plain = Notification {
+                        with_sound: false,
+                        ..notification
+                    };
+                    let _ = frontend.desktop_channel().send(&plain);
+                }
+            }
+        });
+    }
+
+    /// 兜底进程检测线程
+    /// 对于没有配置 hooks 的 CLI（如 Codex），通过进程检测来补充状态
+    fn spawn_fallback_scanner(&self) {
+        let cli_states_bg = self.cli_states.clone();
+        let ipc_sender_bg = self.ipc_sender.clone();
+        let config = self.config.clone();
+        let clock = self.clock.clone();
+
+        std::thread::spawn(move || loop {
+            clock.sleep(Duration::from_secs(config.get_fallback_scan_interval()));
+
+            // 获取所有 CLI 进程（同一个 CLI 的每个实例单独一项）
+            let registry = config.get_cli_registry();
+            let processes = process_monitor::get_cli_processes(&registry);
+            let messages = {
+                let states = cli_states_bg.lock().unwrap();
+                fallback_messages(&processes, &states, &registry)
+            };
+
+            for msg in messages {
+                match msg.event {
+                    CliEvent::SessionEnd => println!(
+                        "Fallback detection: {} {} exited or reported by hooks",
+                        msg.cli,
+                        msg.session_id.as_deref().unwrap_or("")
+                    ),
+                    _ => println!(
+                        "Fallback detection: {} (PID: {:?}, CWD: {:?})",
+                        msg.cli, msg.pid, msg.cwd
+                    ),
+                }
+                let _ = ipc_sender_bg.send(msg);
+            }
+        });
+    }
+
+    /// 定期采样每个在线会话的 CPU、内存和子进程数，写入 CliStatus
+    fn spawn_resource_sampler(&self, frontend: Arc<dyn Frontend>) {
+        let cli_states = self.cli_states.clone();
+        let config = self.config.clone();
+        let clock = self.clock.clone();
+
+        std::thread::spawn(move || {
+            let mut sampler = ResourceSampler::default();
+            loop {
+                sampler.refresh();
+                let processes = sampler.cli_processes(&config.get_cli_registry());
+
+                let now = clock.now();
+                let mut states = cli_states.lock().unwrap();
+                let mut sampled = false;
+                for status in states.values_mut() {
+                    status.resources = match status.state {
+                        CliState::Offline => None,
+                        _ => resource_monitor::session_pid(status, &processes)
+                            .and_then(|pid| sampler.usage(pid)),
+                    };
+                    if status.resources.is_some_and(|usage| usage.is_active()) {
+                        status.last_cpu_activity = Some(now);
+                    }
+                    sampled |= status.resources.is_some();
+                }
+                drop(states);
+
+                if sampled {
+                    frontend.resources_sampled();
+                }
+
+                clock.sleep(Duration::from_secs(config.get_resource_sample_interval()));
+            }
+        });
+    }
+
+    /// 按升级阶梯提醒长时间没有回应的等待
+    fn spawn_wait_escalation(&self, frontend: Arc<dyn Frontend>) {
+        let engine = self.clone();
+
+        std::thread::spawn(move || {
+            let mut escalator = Escalator::default();
+            loop {
+                engine.clock.sleep(ESCALATION_TICK);
+
+                let steps = engine.config.get_wait_escalation();
+                let registry = engine.config.get_cli_registry();
+                escalator.set_quiet(engine.is_quiet());
+                let due: Vec<_> = {
+                    let states = engine.cli_states.lock().unwrap();
+                    escalator
+                        .poll(&steps, &states, engine.clock.now())
+                        .into_iter()
+                        .filter_map(|step| {
+                            let status = states.get(&step.key)?;
+                            Some((step, status.clone()))
+                        })
+                        .collect()
+                };
+
+                let lang = engine.config.get_language();
+                let s = get_strings(lang);
+                for (step, status) in due {
+                    println!(
+                        "Wait escalation: {} {:?} after {}s",
+                        step.key,
+                        step.action,
+                        step.waited.as_secs()
+                    );
+                    match step.action {
+                        // 桌面通知和提示音受"声音通知"开关控制
+                        EscalationAction::Notify if engine.config.get_sound_enabled() => {
+                            let vars = TemplateVars::session(lang, &status, &registry, step.waited);
+                            engine.notify_reach(
+                                frontend.desktop_channel(),
+                                engine.templated(
+                                    TemplateKind::CliWaiting,
+                                    NotifyEvent::CliWaiting,
+                                    (s.app_name, s.cli_waiting_for),
+                                    &vars,
+                                    false,
+                                ),
+                                false,
+                            );
+                        }
+                        EscalationAction::Sound if engine.config.get_sound_enabled() => {
+                            notification::play_system_sound();
+                        }
+                        // 智能置顶：使用 PID 和 CWD 激活正确的应用和窗口
+                        EscalationAction::BringToFront
+                            if engine.config.get_auto_bring_to_front() =>
+                        {
+                            let _ = window_manager::bring_cli_to_front(
+                                status.pid,
+                                status.cwd.as_deref(),
+                                &registry,
+                            );
+                        }
+                        EscalationAction::Push => {
+                            let vars = TemplateVars::session(lang, &status, &registry, step.waited);
+                            engine.notify_reach(
+                                frontend.desktop_channel(),
+                                engine.templated(
+                                    TemplateKind::CliWaitingPush,
+                                    NotifyEvent::CliWaiting,
+                                    (s.app_name, s.cli_waiting_long),
+                                    &vars,
+                                    false,
+                                ),
+                                true,
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        });
+    }
+
+    /// 检测免打扰的开始和结束，结束时把期间暂缓的提醒合并成一条发送
+    fn spawn_quiet_watch(&self, frontend: Arc<dyn Frontend>) {
+        let engine = self.clone();
+
+        std::thread::spawn(move || loop {
+            let quiet = engine.is_quiet();
+            let (changed, held) = {
+                let mut queue = engine.quiet_queue.lock().unwrap();
+                let changed = queue.update(quiet);
+                let held = if changed && !quiet {
+                    queue.take()
+                } else {
+                    Vec::new()
+                };
+                (changed, held)
+            };
+
+            if changed {
+                println!("Quiet: {}", if quiet { "started" } else { "ended" });
+                if !held.is_empty() {
+                    let s = get_strings(engine.config.get_language());
+                    let vars = TemplateVars::new()
+                        .set("count", held.len().to_string())
+                        .set("items", quiet_hours::summary_body(&held));
+                    engine.notify(
+                        frontend.desktop_channel(),
+                        engine.templated(
+                            TemplateKind::QuietSummary,
+                            NotifyEvent::QuietSummary,
+                            (s.quiet_summary_title, "{items}"),
+                            &vars,
+                            engine.config.get_sound_enabled(),
+                        ),
+                    );
+                }
+                frontend.quiet_changed();
+            }
+
+            engine.clock.sleep(QUIET_CHECK_INTERVAL);
+        });
+    }
+
+    /// 单独的久坐计时线程
+    fn spawn_sitting_timer(&self, frontend: Arc<dyn Frontend>) {
+        let engine = self.clone();
+
+        std::thread::spawn(move || {
+            // 如果启用了智能久坐提醒，检查权限并启动活动监听线程（但不开始监控）
+            if engine.config.get_sitting_reminder_enabled() {
+                if !activity_monitor::check_accessibility_permission() {
+                    activity_monitor::request_accessibility_permission();
+                }
+                // 只启动监听线程，不开始监控（按需监控）
+                engine.activity_monitor.start();
+            }
+
+            loop {
+                engine.clock.sleep(Duration::from_secs(60));
+
+                // 检查智能久坐提醒状态
+                engine.check_standup();
+
+                let minutes = {
+                    let mut m = engine.sitting_minutes.lock().unwrap();
+                    *m += 1;
+                    *m
+                };
+                engine
+                    .subscribers
+                    .broadcast(&IpcEvent::SittingTick { minutes });
+
+                frontend.sitting_tick(minutes);
+            }
+        });
+    }
+
+    /// 提醒发出一段时间（默认 2 分钟）后检查用户是否已休息
+    fn check_standup(&self) {
+        let window = self.config.get_standup_check_secs();
+        let mut reminder = self.sitting_reminder.lock().unwrap();
+        if !reminder.awaiting_standup {
+            return;
+        }
+        if let Some(sent_at) = reminder.reminder_sent_at {
+            // 检查是否已过检查窗口
+            if self
+                .clock
+                .now()
+                .saturating_duration_since(sent_at)
+                .as_secs()
+                >= window
+            {
+                // 检查用户是否在这段时间内无活动
+                if self.activity_monitor.is_inactive_for(window) {
+                    // 用户站起来了，重置计时
+                    let previous = std::mem::replace(&mut *self.sitting_minutes.lock().unwrap(), 0);
+                    self.record_history(HistoryRecord::SittingReset {
+                        ts: self.clock.unix_secs(),
+                        minutes: previous,
+                        reason: ResetReason::Break,
+                    });
+                    println!("[久坐提醒] 用户已休息，重置久坐计时");
+                } else {
+                    // 用户仍在活动，继续计时
+                    println!("[久坐提醒] 用户仍在活动，继续计时");
+                }
+                // 停止监控键鼠活动
+                self.activity_monitor.stop_monitoring();
+                // 清除等待状态
+                reminder.awaiting_standup = false;
+                reminder.reminder_sent_at = None;
+            }
+        }
+    }
+
+    /// 启动时和之后每天清理一次超过保留天数的历史记录
+    fn spawn_history_pruner(&self) {
+        let engine = self.clone();
+
+        std::thread::spawn(move || loop {
+            let retention = engine.config.get_history_retention_days() * 24 * 3600;
+            let cutoff = engine.clock.unix_secs().saturating_sub(retention);
+            match engine.history.prune_before(cutoff) {
+                Ok(0) => {}
+                Ok(n) => println!("History: pruned {} records", n),
+                Err(e) => eprintln!("History: {}", e),
+            }
+            engine.clock.sleep(Duration::from_secs(24 * 3600));
+        });
+    }
+
+    /// 每天 0 点过后为刚结束的一天生成日报，周日结束时同时生成周报
+    fn spawn_daily_report(&self) {
+        let engine = self.clone();
+
+        std::thread::spawn(move || loop {
+            let now = engine.clock.unix_secs();
+            let tomorrow = analytics::local_date(now) + Days::new(1);
+            let next_run = analytics::local_day_start(tomorrow) + 60;
+            engine
+                .clock
+                .sleep(Duration::from_secs(next_run.saturating_sub(now).max(60)));
+
+            if !engine.config.get_auto_daily_report() {
+                continue;
+            }
+            let yesterday = engine.today() - Days::new(1);
+            if let Err(e) = engine.write_report(ReportPeriod::Daily, yesterday) {
+                eprintln!("Report: {}", e);
+            }
+            if yesterday.weekday() == Weekday::Sun {
+                if let Err(e) = engine.write_report(ReportPeriod::Weekly, yesterday) {
+                    eprintln!("Report: {}", e);
+                }
+            }
+        });
+    }
+}
+
+/// 兜底检测会话的 session_id
+fn fallback_session_id(pid: u32) -> String {
+    format!("{}{}", FALLBACK_SESSION_PREFIX, pid)
+}
+
+/// 比较兜底检测到的进程和当前会话，生成需要发送的消息：
+/// - 没有被任何会话覆盖、且没有配置 hooks 的进程：按 PID 新建会话（Working）
+/// - 进程已退出的会话（见 ended_sessions）：SessionEnd
+fn fallback_messages(
+    processes: &[ProcessInfo],
+    states: &HashMap<String, CliStatus>,
+    registry: &CliRegistry,
+) -> Vec<CliMessage> {
+    let mut messages = Vec::new();
+
+    for process in processes {
+        if is_hooked(process, states)
+            || registry
+                .get(&process.cli_id)
+                .is_some_and(|d| d.hooks_expected)
+        {
+            continue;
+        }
+        let tracked =
+            active_sessions(states).any(|s| s.is_fallback() && s.pid == Some(process.pid));
+        if !tracked {
+            let mut msg = CliMessage::new(process.cli_id.clone(), CliEvent::Working);
+            msg.session_id = Some(fallback_session_id(process.pid));
+            msg.pid = Some(process.pid);
+            msg.cwd = process.cwd.clone();
+            messages.push(msg);
+        }
+    }
+
+    for key in ended_sessions(processes, states) {
+        messages.push(session_end_message(&states[key]));
+    }
+    messages
+}
+
+/// 结束会话的消息
+fn session_end_message(status: &CliStatus) -> CliMessage {
+    let mut msg = CliMessage::new(status.cli_name.clone(), CliEvent::SessionEnd);
+    msg.session_id = status.session_id.clone();
+    msg.pid = status.pid;
+    msg
+}
+
+fn active_sessions(states: &HashMap<String, CliStatus>) -> impl Iterator<Item = &CliStatus> {
+    states.values().filter(|s| s.state != CliState::Offline)
+}
+
+fn same_cli(process: &ProcessInfo, status: &CliStatus) -> bool {
+    process.cli_id.eq_ignore_ascii_case(&status.cli_name)
+}
+
+/// hooks 上报的会话是否覆盖了这个进程（同 PID，或同 CLI、同 cwd）
+fn is_hooked(process: &ProcessInfo, states: &HashMap<String, CliStatus>) -> bool {
+    active_sessions(states).any(|s| {
+        !s.is_fallback()
+            && same_cli(process, s)
+            && (s.pid == Some(process.pid) || (process.cwd.is_some() && s.cwd == process.cwd))
+    })
+}
+
+/// 对应进程已经不在的在线会话（返回 key）：
+/// - 兜底会话：PID 已退出，或已被 hooks 上报的会话取代
+/// - hooks 会话：PID 不是存活的 CLI 进程，且没有同 CLI 的进程匹配它的 cwd
+///   （没有 cwd 时，同名进程都不在才算退出）
+fn ended_sessions<'a>(
+    processes: &[ProcessInfo],
+    states: &'a HashMap<String, CliStatus>,
+) -> Vec<&'a String> {
+    states
+        .iter()
+        .filter(|(_, s)| s.state != CliState::Offline)
+        .filter(|(_, status)| {
+            if status.is_fallback() {
+                return match processes.iter().find(|p| Some(p.pid) == status.pid) {
+                    Some(process) => is_hooked(process, states),
+                    None => true,
+                };
+            }
+            let candidates: Vec<&ProcessInfo> =
+                processes.iter().filter(|p| same_cli(p, status)).collect();
+            if status
+                .pid
+                .is_some_and(|pid| candidates.iter().any(|p| p.pid == pid))
+            {
+                return false;
+            }
+            match &status.cwd {
+                // 有 cwd 时，只有同名进程都报告了 cwd 且都不匹配才认为退出
+                Some(cwd) => {
+                    candidates.is_empty()
+                        || (candidates.iter().all(|p| p.cwd.is_some())
+                            && !candidates.iter().any(|p| p.cwd.as_ref() == Some(cwd)))
+                }
+                None => candidates.is_empty(),
+            }
+        })
+        .map(|(key, _)| key)
+        .collect()
+}
@@ -0,0 +1,302 @@
+//! 等待提醒的升级阶梯
+//!
+//! 会话进入等待输入后，按配置的时间点依次执行：桌面通知、提示音、终端置顶、
+//! 推送到手机等远程渠道。会话回到 Working（或下线）时阶梯重置，下次等待从头开始。
+//! 免打扰期间阶梯停在桌面通知之后，其余步骤等免打扰结束再补上。
+
+use crate::state_manager::{CliState, CliStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 阶梯中的一步
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EscalationAction {
+    /// 桌面通知（不带声音）
+    Notify,
+    /// 播放提示音
+    Sound,
+    /// 把会话所在的终端窗口置顶
+    BringToFront,
+    /// 发到桌面以外的通知渠道（webhook、ntfy 等）
+    Push,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EscalationStep {
+    /// 开始等待后多少秒执行
+    pub after_secs: u64,
+    pub action: EscalationAction,
+}
+
+/// 默认阶梯：0 秒通知、1 分钟提示音、2 分钟置顶、5 分钟推送
+pub fn default_ladder() -> Vec<EscalationStep> {
+    vec![
+        EscalationStep {
+            after_secs: 0,
+            action: EscalationAction::Notify,
+        },
+        EscalationStep {
+            after_secs: 60,
+            action: EscalationAction::Sound,
+        },
+        EscalationStep {
+            after_secs: 120,
+            action: EscalationAction::BringToFront,
+        },
+        EscalationStep {
+            after_secs: 300,
+            action: EscalationAction::Push,
+        },
+    ]
+}
+
+/// 需要执行的一步
+#[derive(Debug, Clone)]
+pub struct Escalation {
+    /// 会话的 key（cli_states 中的键）
+    pub key: String,
+    pub action: EscalationAction,
+    /// 已等待的时长
+    pub waited: Duration,
+}
+
+/// 一个会话当前的等待
+struct Ladder {
+    since: Instant,
+    /// 已执行的步数
+    fired: usize,
+}
+
+/// 跟踪每个会话的等待时长和已执行的步骤
+#[derive(Default)]
+pub struct Escalator {
+    ladders: HashMap<String, Ladder>,
+    quiet: bool,
+}
+
+impl Escalator {
+    /// 设置是否处于免打扰：期间只执行桌面通知（由引擎放进免打扰队列），
+    /// 提示音、置顶和推送不算已执行，免打扰结束后的第一次 poll 一起返回
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
+    /// 返回到期需要执行的步骤，steps 需按 after_secs 升序排列
+    /// 长时间没有调用（如休眠唤醒）时，已过期的步骤会一起返回
+    pub fn poll(
+        &mut self,
+        steps: &[EscalationStep],
+        sessions: &HashMap<String, CliStatus>,
+        now: Instant,
+    ) -> Vec<Escalation> {
+        self.ladders
+            .retain(|key, _| sessions.get(key).is_some_and(is_waiting));
+
+        let mut due = Vec::new();
+        for (key, status) in sessions {
+            if !is_waiting(status) {
+                continue;
+            }
+            let ladder = self.ladders.entry(key.clone()).or_insert(Ladder {
+                since: now,
+                fired: 0,
+            });
+            let waited = now.saturating_duration_since(ladder.since);
+            while let Some(step) = steps.get(ladder.fired) {
+                if waited < Duration::from_secs(step.after_secs) {
+                    break;
+                }
+                if self.quiet && step.action != EscalationAction::Notify {
+                    break;
+                }
+                due.push(Escalation {
+                    key: key.clone(),
+                    action: step.action,
+                    waited,
+                });
+                ladder.fired += 1;
+            }
+        }
+        due
+    }
+}
+
+/// 等待输入和空闲都算在同一次等待里
+fn is_waiting(status: &CliStatus) -> bool {
+    matches!(status.state, CliState::WaitingInput | CliState::Idle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sessions(state: CliState) -> HashMap<String, CliStatus> {
+        let mut status = CliStatus::with_details("claude".to_string(), Some("a".to_string()), None);
+        status.state = state;
+        HashMap::from([("claude:a".to_string(), status)])
+    }
+
+    fn actions(due: &[Escalation]) -> Vec<EscalationAction> {
+        due.iter().map(|e| e.action).collect()
+    }
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test]
+    fn ladder_fires_each_step_once_when_due() {
+        let steps = default_ladder();
+        let waiting = sessions(CliState::WaitingInput);
+        let start = Instant::now();
+        let mut escalator = Escalator::default();
+
+        assert_eq!(
+            actions(&escalator.poll(&steps, &waiting, start)),
+            [EscalationAction::Notify]
+        );
+        assert!(escalator
+            .poll(&steps, &waiting, start + secs(59))
+            .is_empty());
+
+        let due = escalator.poll(&steps, &waiting, start + secs(60));
+        assert_eq!(actions(&due), [EscalationAction::Sound]);
+        assert_eq!(due[0].key, "claude:a");
+        assert_eq!(due[0].waited, secs(60));
+        assert!(escalator
+            .poll(&steps, &waiting, start + secs(61))
+            .is_empty());
+
+        // 空闲和等待输入算同一次等待
+        let idle = sessions(CliState::Idle);
+        assert_eq!(
+            actions(&escalator.poll(&steps, &idle, start + secs(120))),
+            [EscalationAction::BringToFront]
+        );
+        assert_eq!(
+            actions(&escalator.poll(&steps, &idle, start + secs(300))),
+            [EscalationAction::Push]
+        );
+        assert!(escalator.poll(&steps, &idle, start + secs(3600)).is_empty());
+    }
+
+    #[test]
+    fn working_resets_the_ladder() {
+        let steps = default_ladder();
+        let start = Instant::now();
+        let mut escalator = Escalator::default();
+
+        escalator.poll(&steps, &sessions(CliState::WaitingInput), start);
+        escalator.poll(&steps, &sessions(CliState::WaitingInput), start + secs(60));
+        assert!(escalator
+            .poll(&steps, &sessions(CliState::Working), start + secs(70))
+            .is_empty());
+
+        // 再次等待时从头开始计时
+        let waiting = sessions(CliState::WaitingInput);
+        assert_eq!(
+            actions(&escalator.poll(&steps, &waiting, start + secs(80))),
+            [EscalationAction::Notify]
+        );
+        assert!(escalator
+            .poll(&steps, &waiting, start + secs(130))
+            .is_empty());
+        assert_eq!(
+            actions(&escalator.poll(&steps, &waiting, start + secs(140))),
+            [EscalationAction::Sound]
+        );
+    }
+
+    #[test]
+    fn ended_session_drops_its_ladder() {
+        let steps = default_ladder();
+        let start = Instant::now();
+        let mut escalator = Escalator::default();
+
+        escalator.poll(&steps, &sessions(CliState::WaitingInput), start);
+        assert!(escalator
+            .poll(&steps, &HashMap::new(), start + secs(10))
+            .is_empty());
+        assert_eq!(
+            actions(&escalator.poll(&steps, &sessions(CliState::WaitingInput), start + secs(20))),
+            [EscalationAction::Notify]
+        );
+    }
+
+    #[test]
+    fn overdue_steps_catch_up_after_sleep() {
+        let steps = default_ladder();
+        let waiting = sessions(CliState::WaitingInput);
+        let start = Instant::now();
+        let mut escalator = Escalator::default();
+
+        escalator.poll(&steps, &waiting, start);
+        // 休眠唤醒后一次返回所有已过期的步骤，按阶梯顺序
+        let due = escalator.poll(&steps, &waiting, start + secs(600));
+        assert_eq!(
+            actions(&due),
+            [
+                EscalationAction::Sound,
+                EscalationAction::BringToFront,
+                EscalationAction::Push,
+            ]
+        );
+        assert!(due.iter().all(|e| e.waited == secs(600)));
+        assert!(escalator
+            .poll(&steps, &waiting, start + secs(601))
+            .is_empty());
+    }
+
+    #[test]
+    fn quiet_holds_steps_after_notify_until_it_ends() {
+        let steps = default_ladder();
+        let waiting = sessions(CliState::WaitingInput);
+        let start = Instant::now();
+        let mut escalator = Escalator::default();
+
+        escalator.set_quiet(true);
+        assert_eq!(
+            actions(&escalator.poll(&steps, &waiting, start)),
+            [EscalationAction::Notify]
+        );
+        assert!(escalator
+            .poll(&steps, &waiting, start + secs(400))
+            .is_empty());
+
+        // 免打扰结束后补上被推迟的步骤，不会丢失
+        escalator.set_quiet(false);
+        let due = escalator.poll(&steps, &waiting, start + secs(401));
+        assert_eq!(
+            actions(&due),
+            [
+                EscalationAction::Sound,
+                EscalationAction::BringToFront,
+                EscalationAction::Push,
+            ]
+        );
+        assert!(escalator
+            .poll(&steps, &waiting, start + secs(402))
+            .is_empty());
+    }
+
+    #[test]
+    fn quiet_still_resets_on_working() {
+        let steps = default_ladder();
+        let start = Instant::now();
+        let mut escalator = Escalator::default();
+
+        escalator.set_quiet(true);
+        escalator.poll(&steps, &sessions(CliState::WaitingInput), start);
+        escalator.poll(&steps, &sessions(CliState::Working), start + secs(100));
+        escalator.set_quiet(false);
+
+        // 会话已经回到 Working 过，免打扰结束后从头开始，不补发旧的步骤
+        let waiting = sessions(CliState::WaitingInput);
+        assert_eq!(
+            actions(&escalator.poll(&steps, &waiting, start + secs(200))),
+            [EscalationAction::Notify]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! 无界面模式（--headless）：不创建窗口和托盘，只运行后台引擎
+//!
+//! 状态通过 IPC socket（status / list_sessions / subscribe）和桌面通知对外提供，
+//! 适合没有系统托盘的 Linux 服务器和工作站。
+
+use crate::channels::{DesktopChannel, NotificationChannel};
+use crate::config::ConfigManager;
+use crate::engine::{Engine, Frontend};
+use crate::ipc_server;
+use crate::state_manager::StateChangeEvent;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 收到 SIGINT/SIGTERM 后置为 true
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_signal(_signal: libc::c_int) {
+    SHUTDOWN.store(true, Ordering::SeqCst);
+}
+
+/// 无界面模式的引擎输出：只发送桌面通知并打印日志
+struct HeadlessFrontend;
+
+impl Frontend for HeadlessFrontend {
+    fn desktop_channel(&self) -> Arc<dyn NotificationChannel> {
+        Arc::new(DesktopChannel)
+    }
+
+    fn state_changed(&self, event: &StateChangeEvent) {
+        if event.state_changed {
+            println!("Aggregate state: {:?}", event.state);
+        }
+    }
+}
+
+/// 以无界面模式运行，直到收到退出信号
+pub fn run_headless() {
+    let config = Arc::new(ConfigManager::new());
+    config.load_from_file();
+
+    let engine = Engine::new(config);
+    let initial_state = engine.scan_initial_processes();
+    println!(
+        "Focus Guard running headless (initial state: {:?})",
+        initial_state
+    );
+
+    engine.start(Arc::new(HeadlessFrontend));
+
+    let handler: extern "C" fn(libc::c_int) = handle_signal;
+    // SAFETY: handle_signal 只写入原子变量，是异步信号安全的
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+        libc::signal(libc::SIGTERM, handler as libc::sighandler_t);
+    }
+
+    while !SHUTDOWN.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    println!("Shutting down");
+    ipc_server::cleanup();
+}
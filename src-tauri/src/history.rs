@@ -0,0 +1,280 @@
+//! 会话历史：追加写入应用数据目录下的 history.jsonl
+//!
+//! 每行一条记录（状态转换、久坐提醒、久坐重置），重启后不会丢失，
+//! 供统计和报表使用。超过保留天数的记录在启动时和每天清理一次。
+
+use crate::config::app_data_dir;
+use crate::state_manager::CliState;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// 一条历史记录，ts 为 Unix 时间戳（秒）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistoryRecord {
+    /// 单个会话的状态转换
+    Transition {
+        ts: u64,
+        cli: String,
+        #[serde(default)]
+        session_id: Option<String>,
+        #[serde(default)]
+        cwd: Option<String>,
+        from: CliState,
+        to: CliState,
+    },
+    /// 发送了久坐提醒
+    SittingReminder { ts: u64, minutes: u32 },
+    /// 久坐计时被重置（手动或检测到休息）
+    SittingReset {
+        ts: u64,
+        minutes: u32,
+        #[serde(default)]
+        reason: ResetReason,
+    },
+}
+
+/// 久坐计时重置的原因
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetReason {
+    /// 托盘菜单或 IPC 手动重置
+    #[default]
+    Manual,
+    /// 提醒后检测到用户离开（站起来休息了）
+    Break,
+}
+
+impl HistoryRecord {
+    pub fn ts(&self) -> u64 {
+        match self {
+            HistoryRecord::Transition { ts, .. }
+            | HistoryRecord::SittingReminder { ts, .. }
+            | HistoryRecord::SittingReset { ts, .. } => *ts,
+        }
+    }
+}
+
+/// 追加写入的历史记录文件
+pub struct HistoryStore {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl HistoryStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file: Mutex::new(None),
+        }
+    }
+
+    /// 默认位置：应用数据目录下的 history.jsonl
+    pub fn default_path() -> PathBuf {
+        app_data_dir().join(HISTORY_FILE)
+    }
+
+    /// 追加一条记录，文件在第一次写入时打开
+    pub fn append(&self, record: &HistoryRecord) -> Result<(), String> {
+        let mut line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap();
+        if file.is_none() {
+            *file = Some(self.open_for_append()?);
+        }
+        let result = file.as_mut().unwrap().write_all(line.as_bytes());
+        if let Err(e) = result {
+            // 文件可能已被删除或移动，下次重新打开
+            *file = None;
+            return Err(format!("Failed to write {}: {}", self.path.display(), e));
+        }
+        Ok(())
+    }
+
+    fn open_for_append(&self) -> Result<File, String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))
+    }
+
+    /// 读取 since（含）之后的记录，无法解析的行会被跳过
+    pub fn read_since(&self, since: u64) -> Vec<HistoryRecord> {
+        let _guard = self.file.lock().unwrap();
+        let file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(_) => return Vec::new(),
+        };
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<HistoryRecord>(&line).ok())
+            .filter(|r| r.ts() >= since)
+            .collect()
+    }
+
+    /// 删除 cutoff 之前的记录，返回删除的条数
+    pub fn prune_before(&self, cutoff: u64) -> Result<usize, String> {
+        let mut file = self.file.lock().unwrap();
+        let data = match fs::read_to_string(&self.path) {
+            Ok(d) => d,
+            Err(_) => return Ok(0),
+        };
+
+        let mut kept = String::new();
+        let mut removed = 0;
+        for line in data.lines() {
+            let keep = match serde_json::from_str::<HistoryRecord>(line) {
+                Ok(record) => record.ts() >= cutoff,
+                Err(_) => false,
+            };
+            if keep {
+                kept.push_str(line);
+                kept.push('\n');
+            } else {
+                removed += 1;
+            }
+        }
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        // 写临时文件后替换，关闭旧句柄以便下次写入新文件
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, kept).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, &self.path)
+            .map_err(|e| format!("Failed to replace {}: {}", self.path.display(), e))?;
+        *file = None;
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_store(name: &str) -> HistoryStore {
+        let dir = std::env::temp_dir().join(format!(
+            "focus-guard-history-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        HistoryStore::new(dir.join(HISTORY_FILE))
+    }
+
+    fn transition(ts: u64, to: CliState) -> HistoryRecord {
+        HistoryRecord::Transition {
+            ts,
+            cli: "claude".to_string(),
+            session_id: Some("s1".to_string()),
+            cwd: None,
+            from: CliState::Working,
+            to,
+        }
+    }
+
+    /// 绕过 append 直接写入一行原始内容
+    fn append_raw(store: &HistoryStore, line: &str) {
+        let mut file = OpenOptions::new().append(true).open(&store.path).unwrap();
+        writeln!(file, "{}", line).unwrap();
+    }
+
+    fn timestamps(records: &[HistoryRecord]) -> Vec<u64> {
+        records.iter().map(HistoryRecord::ts).collect()
+    }
+
+    #[test]
+    fn appended_records_read_back_in_order() {
+        let store = scratch_store("append");
+        assert!(store.read_since(0).is_empty());
+
+        store
+            .append(&transition(100, CliState::WaitingInput))
+            .unwrap();
+        store
+            .append(&HistoryRecord::SittingReminder {
+                ts: 200,
+                minutes: 45,
+            })
+            .unwrap();
+        store
+            .append(&HistoryRecord::SittingReset {
+                ts: 300,
+                minutes: 50,
+                reason: ResetReason::Break,
+            })
+            .unwrap();
+
+        let records = store.read_since(0);
+        assert_eq!(timestamps(&records), [100, 200, 300]);
+        match &records[0] {
+            HistoryRecord::Transition { session_id, to, .. } => {
+                assert_eq!(session_id.as_deref(), Some("s1"));
+                assert_eq!(*to, CliState::WaitingInput);
+            }
+            other => panic!("unexpected record {:?}", other),
+        }
+        assert!(matches!(
+            records[2],
+            HistoryRecord::SittingReset {
+                reason: ResetReason::Break,
+                ..
+            }
+        ));
+        // since 本身包含在内
+        assert_eq!(store.read_since(200).len(), 2);
+    }
+
+    #[test]
+    fn corrupt_lines_are_skipped() {
+        let store = scratch_store("corrupt");
+        store
+            .append(&transition(100, CliState::WaitingInput))
+            .unwrap();
+        // 写到一半被中断的行，以及缺少 reason 的旧格式记录
+        append_raw(&store, r#"{"kind":"transition","ts":1"#);
+        append_raw(&store, r#"{"kind":"sitting_reset","ts":150,"minutes":30}"#);
+        store.append(&transition(200, CliState::Working)).unwrap();
+
+        let records = store.read_since(0);
+        assert_eq!(timestamps(&records), [100, 150, 200]);
+        assert!(matches!(
+            records[1],
+            HistoryRecord::SittingReset {
+                reason: ResetReason::Manual,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn prune_drops_old_and_corrupt_lines() {
+        let store = scratch_store("prune");
+        assert_eq!(store.prune_before(100), Ok(0));
+
+        store
+            .append(&transition(50, CliState::WaitingInput))
+            .unwrap();
+        append_raw(&store, "not json");
+        store.append(&transition(150, CliState::Working)).unwrap();
+
+        assert_eq!(store.prune_before(100), Ok(2));
+        assert_eq!(store.prune_before(100), Ok(0));
+        // 替换文件后继续追加写入新文件
+        store.append(&transition(250, CliState::Idle)).unwrap();
+        let records = store.read_since(0);
+        assert_eq!(timestamps(&records), [150, 250]);
+    }
+}
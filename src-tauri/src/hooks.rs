@@ -0,0 +1,818 @@
+//! `focus-guard hooks` 子命令：为 Claude Code / Gemini CLI / Codex 安装或移除 hooks
+//!
+//! 对配置文件做结构化合并，只改动 Focus Guard 自己的条目，
+//! 修改前会打印 diff 并保留带时间戳的备份。
+
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use toml_edit::DocumentMut;
+
+/// 识别 Focus Guard 条目的标记（命令中包含 notify 客户端的名字）
+const NOTIFY_MARKER: &str = "focus-guard-notify";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Install,
+    Uninstall,
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TargetCli {
+    Claude,
+    Gemini,
+    Codex,
+}
+
+impl TargetCli {
+    const ALL: [TargetCli; 3] = [TargetCli::Claude, TargetCli::Gemini, TargetCli::Codex];
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "claude" => Some(TargetCli::Claude),
+            "gemini" => Some(TargetCli::Gemini),
+            "codex" => Some(TargetCli::Codex),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            TargetCli::Claude => "claude",
+            TargetCli::Gemini => "gemini",
+            TargetCli::Codex => "codex",
+        }
+    }
+
+    fn config_dir(&self, home: &Path) -> PathBuf {
+        match self {
+            TargetCli::Claude => home.join(".claude"),
+            TargetCli::Gemini => home.join(".gemini"),
+            TargetCli::Codex => home.join(".codex"),
+        }
+    }
+
+    fn config_file(&self, home: &Path) -> PathBuf {
+        match self {
+            TargetCli::Claude | TargetCli::Gemini => self.config_dir(home).join("settings.json"),
+            TargetCli::Codex => self.config_dir(home).join("config.toml"),
+        }
+    }
+}
+
+/// 单个 hook：CLI 的事件名 -> Focus Guard 事件
+struct HookSpec {
+    event: &'static str,
+    matcher: Option<&'static str>,
+    notify_event: &'static str,
+}
+
+const CLAUDE_HOOKS: &[HookSpec] = &[
+    HookSpec {
+        event: "SessionStart",
+        matcher: None,
+        notify_event: "session_start",
+    },
+    HookSpec {
+        event: "SessionEnd",
+        matcher: None,
+        notify_event: "session_end",
+    },
+    HookSpec {
+        event: "Stop",
+        matcher: None,
+        notify_event: "stop",
+    },
+    HookSpec {
+        event: "PreToolUse",
+        matcher: Some("*"),
+        notify_event: "working",
+    },
+    HookSpec {
+        event: "Notification",
+        matcher: Some("idle_prompt"),
+        notify_event: "idle_prompt",
+    },
+    HookSpec {
+        event: "Notification",
+        matcher: Some("permission_prompt"),
+        notify_event: "permission_prompt",
+    },
+];
+
+const GEMINI_HOOKS: &[HookSpec] = &[
+    HookSpec {
+        event: "SessionStart",
+        matcher: None,
+        notify_event: "session_start",
+    },
+    HookSpec {
+        event: "SessionEnd",
+        matcher: None,
+        notify_event: "session_end",
+    },
+    HookSpec {
+        event: "BeforeAgent",
+        matcher: None,
+        notify_event: "working",
+    },
+    HookSpec {
+        event: "AfterAgent",
+        matcher: None,
+        notify_event: "stop",
+    },
+    HookSpec {
+        event: "BeforeTool",
+        matcher: None,
+        notify_event: "working",
+    },
+    HookSpec {
+        event: "AfterTool",
+        matcher: None,
+        notify_event: "stop",
+    },
+];
+
+struct Options {
+    action: Action,
+    clis: Vec<TargetCli>,
+    dry_run: bool,
+    notify_path: PathBuf,
+}
+
+/// 子命令入口，返回进程退出码
+pub fn run(args: &[String]) -> i32 {
+    let options = match parse_args(args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}", e);
+            print_usage();
+            return 2;
+        }
+    };
+
+    let home = match std::env::var_os("HOME") {
+        Some(h) => PathBuf::from(h),
+        None => {
+            eprintln!("HOME is not set");
+            return 1;
+        }
+    };
+
+    // 未指定 --cli 时只处理已安装（配置目录存在）的 CLI
+    let clis: Vec<TargetCli> = if options.clis.is_empty() {
+        TargetCli::ALL
+            .into_iter()
+            .filter(|c| c.config_dir(&home).is_dir())
+            .collect()
+    } else {
+        options.clis.clone()
+    };
+    if clis.is_empty() {
+        println!("No supported CLI configuration found (use --cli to choose one)");
+        return 0;
+    }
+
+    if options.action == Action::Install && !options.notify_path.exists() {
+        println!(
+            "Warning: notify client not found at {}",
+            options.notify_path.display()
+        );
+    }
+
+    let mut exit_code = 0;
+    for cli in clis {
+        let result = match options.action {
+            Action::Status => print_status(cli, &home, &options.notify_path),
+            Action::Install | Action::Uninstall => apply(cli, &home, &options),
+        };
+        if let Err(e) = result {
+            eprintln!("[{}] {}", cli.name(), e);
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
+
+fn print_usage() {
+    eprintln!("Usage: focus-guard hooks <install|uninstall|status> [--cli claude|gemini|codex]... [--dry-run] [--notify-path <path>]");
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut iter = args.iter();
+    let action = match iter.next().map(String::as_str) {
+        Some("install") => Action::Install,
+        Some("uninstall") => Action::Uninstall,
+        Some("status") => Action::Status,
+        Some(other) => return Err(format!("Unknown hooks action: {}", other)),
+        None => return Err("Missing hooks action".to_string()),
+    };
+
+    let mut clis = Vec::new();
+    let mut dry_run = false;
+    let mut notify_path = None;
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--cli" => {
+                let name = iter.next().ok_or("--cli needs a value")?;
+                for part in name.split(',') {
+                    let cli = TargetCli::parse(part)
+                        .ok_or_else(|| format!("Unsupported CLI: {}", part))?;
+                    if !clis.contains(&cli) {
+                        clis.push(cli);
+                    }
+                }
+            }
+            "--dry-run" => dry_run = true,
+            "--notify-path" => {
+                let path = iter.next().ok_or("--notify-path needs a value")?;
+                notify_path = Some(PathBuf::from(path));
+            }
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+
+    let notify_path = match notify_path {
+        Some(p) => p,
+        None => default_notify_path()?,
+    };
+
+    Ok(Options {
+        action,
+        clis,
+        dry_run,
+        notify_path,
+    })
+}
+
+/// 默认使用与主程序同目录的 notify 客户端
+fn default_notify_path() -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    Ok(exe.with_file_name(NOTIFY_MARKER))
+}
+
+/// 生成 hook 命令，路径含特殊字符时加引号
+fn notify_command(notify_path: &Path, cli: TargetCli, event: &str) -> String {
+    format!(
+        "{} {} {}",
+        shell_quote(&notify_path.to_string_lossy()),
+        cli.name(),
+        event
+    )
+}
+
+fn shell_quote(s: &str) -> String {
+    let safe = s
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-+:@".contains(c));
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+/// 安装或卸载，并打印 diff
+fn apply(cli: TargetCli, home: &Path, options: &Options) -> Result<(), String> {
+    let path = cli.config_file(home);
+    let original = match fs::read_to_string(&path) {
+        Ok(s) => Some(s),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let old_text = original.clone().unwrap_or_default();
+
+    let install = options.action == Action::Install;
+    let new_text = match cli {
+        TargetCli::Claude | TargetCli::Gemini => {
+            update_json_settings(cli, &old_text, install, &options.notify_path)?
+        }
+        TargetCli::Codex => update_codex_config(&old_text, install, &options.notify_path)?,
+    };
+
+    if new_text == old_text || (original.is_none() && !install) {
+        println!("[{}] {} is already up to date", cli.name(), path.display());
+        return Ok(());
+    }
+
+    println!("[{}] {}", cli.name(), path.display());
+    print_diff(&old_text, &new_text);
+
+    if options.dry_run {
+        println!("[{}] dry run, nothing written", cli.name());
+        return Ok(());
+    }
+
+    if original.is_some() {
+        let backup = backup_path(&path);
+        fs::copy(&path, &backup).map_err(|e| format!("Failed to back up settings: {}", e))?;
+        println!("[{}] backup saved to {}", cli.name(), backup.display());
+    } else if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    // 先写临时文件再重命名，避免写到一半时损坏配置
+    let tmp = path.with_extension("focus-guard.tmp");
+    fs::write(&tmp, &new_text).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+
+    println!(
+        "[{}] hooks {}",
+        cli.name(),
+        if install { "installed" } else { "removed" }
+    );
+    Ok(())
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut backup = path.with_file_name(format!("{}.focus-guard-{}.bak", name, timestamp));
+    // 同一秒内多次修改时追加序号，避免覆盖之前的备份
+    let mut counter = 1;
+    while backup.exists() {
+        backup = path.with_file_name(format!(
+            "{}.focus-guard-{}-{}.bak",
+            name, timestamp, counter
+        ));
+        counter += 1;
+    }
+    backup
+}
+
+fn is_focus_guard_command(value: &Value) -> bool {
+    value
+        .get("command")
+        .and_then(|c| c.as_str())
+        .is_some_and(|c| c.contains(NOTIFY_MARKER))
+}
+
+/// 判断一个 hook 条目是否包含 Focus Guard 的命令
+/// 兼容两种格式：{"matcher":..,"hooks":[{"command":..}]} 和扁平的 {"command":..}
+fn is_focus_guard_entry(entry: &Value) -> bool {
+    if is_focus_guard_command(entry) {
+        return true;
+    }
+    entry
+        .get("hooks")
+        .and_then(|h| h.as_array())
+        .is_some_and(|hooks| hooks.iter().any(is_focus_guard_command))
+}
+
+/// 从事件列表中移除 Focus Guard 的命令，分组内只剩我们的命令时整组移除
+fn strip_focus_guard_entries(list: &mut Vec<Value>) {
+    list.retain_mut(|entry| {
+        if is_focus_guard_command(entry) {
+            return false;
+        }
+        match entry.get_mut("hooks").and_then(|h| h.as_array_mut()) {
+            Some(hooks) if hooks.iter().any(is_focus_guard_command) => {
+                hooks.retain(|h| !is_focus_guard_command(h));
+                !hooks.is_empty()
+            }
+            _ => true,
+        }
+    });
+}
+
+fn hook_specs(cli: TargetCli) -> &'static [HookSpec] {
+    match cli {
+        TargetCli::Claude => CLAUDE_HOOKS,
+        TargetCli::Gemini => GEMINI_HOOKS,
+        TargetCli::Codex => &[],
+    }
+}
+
+/// 生成一个 hook 条目，格式与 scripts/setup-hooks.sh 保持一致
+fn hook_entry(cli: TargetCli, spec: &HookSpec, notify_path: &Path) -> Value {
+    let command = notify_command(notify_path, cli, spec.notify_event);
+    match cli {
+        TargetCli::Gemini => json!({ "command": command }),
+        _ => {
+            let mut entry = Map::new();
+            if let Some(matcher) = spec.matcher {
+                entry.insert("matcher".to_string(), json!(matcher));
+            }
+            entry.insert(
+                "hooks".to_string(),
+                json!([{ "type": "command", "command": command }]),
+            );
+            Value::Object(entry)
+        }
+    }
+}
+
+/// 合并 settings.json：先移除旧的 Focus Guard 条目，安装时再追加新的
+fn update_json_settings(
+    cli: TargetCli,
+    text: &str,
+    install: bool,
+    notify_path: &Path,
+) -> Result<String, String> {
+    let mut root: Value = if text.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(text).map_err(|e| format!("Invalid JSON in settings: {}", e))?
+    };
+    let root_obj = root
+        .as_object_mut()
+        .ok_or("Settings file is not a JSON object")?;
+
+    let hooks = root_obj
+        .entry("hooks".to_string())
+        .or_insert_with(|| json!({}));
+    let hooks_obj = hooks
+        .as_object_mut()
+        .ok_or("\"hooks\" is not a JSON object")?;
+
+    // 移除已有的 Focus Guard 条目
+    for entries in hooks_obj.values_mut() {
+        if let Some(list) = entries.as_array_mut() {
+            strip_focus_guard_entries(list);
+        }
+    }
+
+    if install {
+        for spec in hook_specs(cli) {
+            let list = hooks_obj
+                .entry(spec.event.to_string())
+                .or_insert_with(|| json!([]));
+            if let Some(list) = list.as_array_mut() {
+                list.push(hook_entry(cli, spec, notify_path));
+            }
+        }
+    }
+
+    // 清理空的事件列表和空的 hooks 对象
+    hooks_obj.retain(|_, v| v.as_array().map(|a| !a.is_empty()).unwrap_or(true));
+    if hooks_obj.is_empty() {
+        root_obj.remove("hooks");
+    }
+
+    if text.trim().is_empty() && root_obj.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut output = serde_json::to_string_pretty(&root).map_err(|e| e.to_string())?;
+    output.push('\n');
+    // 内容没有实际变化时保留原文（避免仅因格式不同而改写）
+    if !text.trim().is_empty() {
+        if let Ok(original) = serde_json::from_str::<Value>(text) {
+            if original == root {
+                return Ok(text.to_string());
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Codex 使用 config.toml 顶层的 notify 数组，只能配置一个程序
+/// 回合结束时 Codex 会把 JSON 作为最后一个参数传给 notify 程序
+fn update_codex_config(text: &str, install: bool, notify_path: &Path) -> Result<String, String> {
+    let mut doc: DocumentMut = text
+        .parse()
+        .map_err(|e| format!("Invalid TOML in config: {}", e))?;
+
+    if let Some(existing) = doc.get("notify") {
+        if !is_focus_guard_notify(existing) {
+            if install {
+                return Err("config.toml already has a notify program; remove it first".to_string());
+            }
+            return Ok(text.to_string());
+        }
+    }
+
+    if install {
+        let command: toml_edit::Array = [
+            notify_path.to_string_lossy().to_string(),
+            "codex".to_string(),
+            "stop".to_string(),
+        ]
+        .into_iter()
+        .collect();
+        // 已有条目时原位替换，否则追加到顶层键的末尾
+        match doc.get_mut("notify") {
+            Some(item) => *item = toml_edit::value(command),
+            None => {
+                doc.insert("notify", toml_edit::value(command));
+            }
+        }
+    } else {
+        doc.remove("notify");
+    }
+
+    let output = doc.to_string();
+    if output == text {
+        return Ok(text.to_string());
+    }
+    if output.trim().is_empty() {
+        return Ok(String::new());
+    }
+    Ok(output)
+}
+
+/// 顶层 notify 是否为 Focus Guard 的命令（可以跨多行）
+fn is_focus_guard_notify(item: &toml_edit::Item) -> bool {
+    item.as_array()
+        .and_then(|a| a.get(0))
+        .and_then(|program| program.as_str())
+        .is_some_and(|program| program.contains(NOTIFY_MARKER))
+}
+
+fn print_status(cli: TargetCli, home: &Path, notify_path: &Path) -> Result<(), String> {
+    let path = cli.config_file(home);
+    let text = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(_) => {
+            println!(
+                "[{}] not installed ({} not found)",
+                cli.name(),
+                path.display()
+            );
+            return Ok(());
+        }
+    };
+
+    let installed: Vec<String> = match cli {
+        TargetCli::Claude | TargetCli::Gemini => {
+            let root: Value = serde_json::from_str(&text)
+                .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?;
+            root.get("hooks")
+                .and_then(|h| h.as_object())
+                .map(|hooks| {
+                    hooks
+                        .iter()
+                        .filter(|(_, entries)| {
+                            entries
+                                .as_array()
+                                .is_some_and(|list| list.iter().any(is_focus_guard_entry))
+                        })
+                        .map(|(event, _)| event.clone())
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+        TargetCli::Codex => {
+            let doc: DocumentMut = text
+                .parse()
+                .map_err(|e| format!("Invalid TOML in {}: {}", path.display(), e))?;
+            doc.get("notify")
+                .filter(|item| is_focus_guard_notify(item))
+                .map(|_| vec!["notify".to_string()])
+                .unwrap_or_default()
+        }
+    };
+
+    if installed.is_empty() {
+        println!("[{}] not installed ({})", cli.name(), path.display());
+    } else {
+        println!(
+            "[{}] installed in {}: {}",
+            cli.name(),
+            path.display(),
+            installed.join(", ")
+        );
+        if !notify_path.exists() {
+            println!(
+                "[{}] warning: notify client {} does not exist",
+                cli.name(),
+                notify_path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 打印逐行 diff（基于最长公共子序列，只显示改动附近的行）
+fn print_diff(old: &str, new: &str) {
+    const CONTEXT: usize = 2;
+
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = a[i..] 与 b[j..] 的最长公共子序列长度
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<(char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            ops.push(('+', b[j]));
+            j += 1;
+        } else {
+            ops.push(('-', a[i]));
+            i += 1;
+        }
+    }
+
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != ' ')
+        .map(|(i, _)| i)
+        .collect();
+    let mut last_printed: Option<usize> = None;
+    for (index, (op, line)) in ops.iter().enumerate() {
+        let near_change = changed
+            .iter()
+            .any(|&c| index + CONTEXT >= c && index <= c + CONTEXT);
+        if !near_change {
+            continue;
+        }
+        if last_printed.is_some_and(|last| index > last + 1) {
+            println!("  ...");
+        }
+        println!("{} {}", op, line);
+        last_printed = Some(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTIFY: &str = "/opt/focus-guard/focus-guard-notify";
+
+    fn notify_path() -> PathBuf {
+        PathBuf::from(NOTIFY)
+    }
+
+    fn install_json(cli: TargetCli, text: &str) -> String {
+        update_json_settings(cli, text, true, &notify_path()).unwrap()
+    }
+
+    fn uninstall_json(cli: TargetCli, text: &str) -> String {
+        update_json_settings(cli, text, false, &notify_path()).unwrap()
+    }
+
+    #[test]
+    fn claude_install_keeps_foreign_hooks() {
+        let original = r#"{
+  "model": "opus",
+  "hooks": {
+    "Stop": [
+      { "hooks": [{ "type": "command", "command": "say done" }] }
+    ]
+  }
+}
+"#;
+        let installed = install_json(TargetCli::Claude, original);
+        let root: Value = serde_json::from_str(&installed).unwrap();
+        assert_eq!(root["model"], "opus");
+
+        let stop = root["hooks"]["Stop"].as_array().unwrap();
+        assert_eq!(stop.len(), 2);
+        assert_eq!(stop[0]["hooks"][0]["command"], "say done");
+        assert_eq!(
+            stop[1]["hooks"][0]["command"],
+            format!("{} claude stop", NOTIFY)
+        );
+
+        let notification = root["hooks"]["Notification"].as_array().unwrap();
+        let matchers: Vec<&str> = notification
+            .iter()
+            .map(|e| e["matcher"].as_str().unwrap())
+            .collect();
+        assert_eq!(matchers, ["idle_prompt", "permission_prompt"]);
+    }
+
+    #[test]
+    fn claude_install_is_idempotent() {
+        let once = install_json(TargetCli::Claude, "");
+        let twice = install_json(TargetCli::Claude, &once);
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn claude_uninstall_restores_foreign_hooks() {
+        let original = r#"{
+  "hooks": {
+    "Stop": [
+      {
+        "hooks": [
+          { "type": "command", "command": "say done" }
+        ]
+      }
+    ]
+  }
+}
+"#;
+        let installed = install_json(TargetCli::Claude, original);
+        let removed = uninstall_json(TargetCli::Claude, &installed);
+        let root: Value = serde_json::from_str(&removed).unwrap();
+        let expected: Value = serde_json::from_str(original).unwrap();
+        assert_eq!(root, expected);
+        assert_eq!(uninstall_json(TargetCli::Claude, &removed), removed);
+    }
+
+    #[test]
+    fn uninstall_keeps_foreign_commands_in_shared_group() {
+        let original = format!(
+            r#"{{"hooks":{{"Stop":[{{"hooks":[
+                {{"type":"command","command":"say done"}},
+                {{"type":"command","command":"{} claude stop"}}
+            ]}}]}}}}"#,
+            NOTIFY
+        );
+        let removed = uninstall_json(TargetCli::Claude, &original);
+        let root: Value = serde_json::from_str(&removed).unwrap();
+        assert_eq!(
+            root["hooks"]["Stop"],
+            json!([{ "hooks": [{ "type": "command", "command": "say done" }] }])
+        );
+    }
+
+    #[test]
+    fn uninstall_drops_empty_hooks_object() {
+        let installed = install_json(TargetCli::Claude, "");
+        assert_eq!(uninstall_json(TargetCli::Claude, &installed), "{}\n");
+    }
+
+    #[test]
+    fn gemini_uses_flat_entries() {
+        let original = r#"{"hooks":{"AfterAgent":[{"command":"notify-send done"}]}}"#;
+        let installed = install_json(TargetCli::Gemini, original);
+        let root: Value = serde_json::from_str(&installed).unwrap();
+        assert_eq!(
+            root["hooks"]["AfterAgent"],
+            json!([
+                { "command": "notify-send done" },
+                { "command": format!("{} gemini stop", NOTIFY) }
+            ])
+        );
+        assert_eq!(install_json(TargetCli::Gemini, &installed), installed);
+
+        let removed = uninstall_json(TargetCli::Gemini, &installed);
+        let root: Value = serde_json::from_str(&removed).unwrap();
+        let expected: Value = serde_json::from_str(original).unwrap();
+        assert_eq!(root, expected);
+    }
+
+    #[test]
+    fn codex_install_adds_top_level_notify() {
+        let original = "model = \"o3\"\n\n[profiles.fast]\nmodel = \"o4-mini\"\n";
+        let installed = update_codex_config(original, true, &notify_path()).unwrap();
+        assert_eq!(
+            installed,
+            format!(
+                "model = \"o3\"\nnotify = [\"{}\", \"codex\", \"stop\"]\n\n[profiles.fast]\nmodel = \"o4-mini\"\n",
+                NOTIFY
+            )
+        );
+        assert_eq!(
+            update_codex_config(&installed, true, &notify_path()).unwrap(),
+            installed
+        );
+        assert_eq!(
+            update_codex_config(&installed, false, &notify_path()).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn codex_handles_multi_line_notify() {
+        let ours = format!(
+            "model = \"o3\"\nnotify = [\n  \"{}\",\n  \"codex\",\n  \"stop\",\n]\n",
+            NOTIFY
+        );
+        let removed = update_codex_config(&ours, false, &notify_path()).unwrap();
+        assert_eq!(removed, "model = \"o3\"\n");
+
+        let foreign = "notify = [\n  \"terminal-notifier\",\n  \"-title\",\n]\n";
+        assert!(update_codex_config(foreign, true, &notify_path()).is_err());
+        assert_eq!(
+            update_codex_config(foreign, false, &notify_path()).unwrap(),
+            foreign
+        );
+    }
+
+    #[test]
+    fn codex_ignores_notify_inside_tables() {
+        let original = "[tui]\nnotify = true\n";
+        let installed = update_codex_config(original, true, &notify_path()).unwrap();
+        let doc: DocumentMut = installed.parse().unwrap();
+        assert!(is_focus_guard_notify(&doc["notify"]));
+        assert_eq!(doc["tui"]["notify"].as_bool(), Some(true));
+    }
+}
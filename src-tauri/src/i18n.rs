@@ -1,17 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub enum Language {
     English,
+    #[default]
     Chinese,
 }
 
-impl Default for Language {
-    fn default() -> Self {
-        Language::Chinese
-    }
-}
-
 impl Language {
     pub fn toggle(&self) -> Self {
         match self {
@@ -33,6 +28,22 @@ pub struct Strings {
     pub smart_sitting_reminder: &'static str,
     pub reminder_interval: &'static str,  // "提醒间隔: {}分钟" / "Interval: {}m"
     pub reset_timer: &'static str,
+    pub reminder_snooze: &'static str,
+    pub reminder_took_break: &'static str,
+    pub reminder_skip: &'static str,
+    pub response_latency: &'static str,
+    pub latency_summary: &'static str, // "今天等待 {} 次，中位数 {}，P90 {}"
+    pub latency_none: &'static str,
+    pub daily_report: &'static str,
+    pub weekly_report: &'static str,
+    pub auto_daily_report: &'static str,
+    pub report_failed: &'static str,
+    pub stalled: &'static str, // 会话名称后的标记
+    pub stall_notify: &'static str,
+    pub clear_stale_sessions: &'static str,
+    pub snooze: &'static str, // "暂停提醒 {} 分钟" / "Snooze for {} min"
+    pub snoozed_until: &'static str, // "提醒已暂停至 {}（点击恢复）"
+    pub meeting_mode: &'static str,
     pub quit: &'static str,
     pub language: &'static str,
 
@@ -40,7 +51,7 @@ pub struct Strings {
     pub check_update: &'static str,
     pub version: &'static str,  // "版本: {}" / "Version: {}"
     pub checking_update: &'static str,
-    pub update_available: &'static str,  // "发现新版本: {}" / "New version: {}"
+    pub update_available: &'static str, // "发现新版本: {version}" / "New version: {version}"
     pub no_update: &'static str,
     pub update_error: &'static str,
     pub downloading: &'static str,
@@ -48,12 +59,24 @@ pub struct Strings {
 
     // Notifications
     pub app_name: &'static str,
-    pub cli_waiting: &'static str,
+    pub cli_waiting_for: &'static str,  // "{label} {state}"
+    pub cli_waiting_long: &'static str, // "{label} 已经等待了 {waited}"
     pub sitting_reminder_title: &'static str,
     pub sitting_reminder_body: &'static str,  // "你已经坐了{}分钟了，起来活动一下吧！"
     pub smart_reminder_title: &'static str,
-    pub smart_reminder_body: &'static str,    // "你已经连续工作{}分钟了！\n休息2分钟后自动重置计时"
+    pub smart_reminder_body: &'static str, // "你已经连续工作{minutes}分钟了！\n休息{reset_minutes}分钟后自动重置计时\n..."
     pub sound_enabled_msg: &'static str,
+    pub cli_stalled_title: &'static str,
+    pub cli_stalled_body: &'static str, // "{label} 长时间没有活动，可能卡住了"
+    pub quiet_summary_title: &'static str,
+
+    // {state} in notification templates
+    pub state_waiting: &'static str,
+    pub state_permission: &'static str,
+    pub state_idle: &'static str,
+    pub state_stalled: &'static str,
+    pub state_working: &'static str,
+    pub state_offline: &'static str,
 }
 
 const ENGLISH: Strings = Strings {
@@ -68,6 +91,22 @@ const ENGLISH: Strings = Strings {
     smart_sitting_reminder: "Smart Sitting Reminder",
     reminder_interval: "Interval: {}m",
     reset_timer: "Reset Timer",
+    reminder_snooze: "Remind Me in 10 Min",
+    reminder_took_break: "I Took a Break",
+    reminder_skip: "Skip This One",
+    response_latency: "Response Time Today",
+    latency_summary: "{} waits today, median {}, p90 {}",
+    latency_none: "No waits recorded today",
+    daily_report: "Generate Daily Report",
+    weekly_report: "Generate Weekly Report",
+    auto_daily_report: "Auto Daily Report",
+    report_failed: "Failed to generate report",
+    stalled: "stalled",
+    stall_notify: "Notify When Stalled",
+    clear_stale_sessions: "Clear Stale Sessions",
+    snooze: "Snooze for {} min",
+    snoozed_until: "Snoozed until {} (Resume)",
+    meeting_mode: "In a Meeting",
     quit: "Quit",
     language: "中文",
 
@@ -75,7 +114,7 @@ const ENGLISH: Strings = Strings {
     check_update: "Check for Updates",
     version: "Version: {}",
     checking_update: "Checking...",
-    update_available: "New version: {}",
+    update_available: "New version: {version}",
     no_update: "Already up to date",
     update_error: "Update check failed",
     downloading: "Downloading...",
@@ -83,12 +122,23 @@ const ENGLISH: Strings = Strings {
 
     // Notifications
     app_name: "Focus Guard",
-    cli_waiting: "CLI is waiting for your input!",
+    cli_waiting_for: "{label} {state}",
+    cli_waiting_long: "{label} has been waiting for {waited}",
     sitting_reminder_title: "Sitting Reminder",
     sitting_reminder_body: "You've been sitting for {} minutes. Time to stretch!",
     smart_reminder_title: "Time for a Break",
-    smart_reminder_body: "You've been working for {} minutes!\nTimer resets after 2 min of inactivity",
+    smart_reminder_body: "You've been working for {minutes} minutes!\nTimer resets after {reset_minutes} min of inactivity\nUse the buttons to snooze, log a break or skip",
     sound_enabled_msg: "Sound notification enabled",
+    cli_stalled_title: "Session May Be Stuck",
+    cli_stalled_body: "{label} has had no events or CPU activity for a while",
+    quiet_summary_title: "Missed While Quiet",
+
+    state_waiting: "is waiting for your input",
+    state_permission: "needs permission",
+    state_idle: "has been idle",
+    state_stalled: "may be stuck",
+    state_working: "is working",
+    state_offline: "has exited",
 };
 
 const CHINESE: Strings = Strings {
@@ -103,6 +153,22 @@ const CHINESE: Strings = Strings {
     smart_sitting_reminder: "智能久坐提醒",
     reminder_interval: "提醒间隔: {}分钟",
     reset_timer: "重置计时",
+    reminder_snooze: "10 分钟后提醒",
+    reminder_took_break: "我休息过了",
+    reminder_skip: "跳过这次",
+    response_latency: "今日响应时间",
+    latency_summary: "今天等待 {} 次，中位数 {}，P90 {}",
+    latency_none: "今天还没有等待记录",
+    daily_report: "生成日报",
+    weekly_report: "生成周报",
+    auto_daily_report: "每天自动生成日报",
+    report_failed: "报表生成失败",
+    stalled: "卡住",
+    stall_notify: "会话卡住时通知",
+    clear_stale_sessions: "清理已结束的会话",
+    snooze: "暂停提醒 {} 分钟",
+    snoozed_until: "提醒已暂停至 {}（点击恢复）",
+    meeting_mode: "会议中",
     quit: "退出",
     language: "English",
 
@@ -110,7 +176,7 @@ const CHINESE: Strings = Strings {
     check_update: "检查更新",
     version: "版本: {}",
     checking_update: "检查中...",
-    update_available: "发现新版本: {}",
+    update_available: "发现新版本: {version}",
     no_update: "已是最新版本",
     update_error: "检查更新失败",
     downloading: "下载中...",
@@ -118,12 +184,23 @@ const CHINESE: Strings = Strings {
 
     // Notifications
     app_name: "Focus Guard",
-    cli_waiting: "CLI正在等待你的输入，请查看终端！",
+    cli_waiting_for: "{label} {state}",
+    cli_waiting_long: "{label} 已经等待了 {waited}",
     sitting_reminder_title: "久坐提醒",
     sitting_reminder_body: "你已经坐了{}分钟了，起来活动一下吧！",
     smart_reminder_title: "该休息了",
-    smart_reminder_body: "你已经连续工作{}分钟了！\n休息2分钟后自动重置计时",
+    smart_reminder_body: "你已经连续工作{minutes}分钟了！\n休息{reset_minutes}分钟后自动重置计时\n可以用按钮稍后提醒、记录休息或跳过",
     sound_enabled_msg: "声音通知已开启",
+    cli_stalled_title: "会话可能卡住了",
+    cli_stalled_body: "{label} 长时间没有事件，也没有 CPU 占用",
+    quiet_summary_title: "免打扰期间的提醒",
+
+    state_waiting: "正在等待你的输入",
+    state_permission: "需要授权",
+    state_idle: "已空闲",
+    state_stalled: "可能卡住了",
+    state_working: "正在工作",
+    state_offline: "已退出",
 };
 
 pub fn get_strings(lang: Language) -> &'static Strings {
@@ -155,17 +232,45 @@ pub fn format_sitting_reminder(lang: Language, minutes: u32) -> String {
     get_strings(lang).sitting_reminder_body.replace("{}", &minutes.to_string())
 }
 
-/// Format smart reminder body
-pub fn format_smart_reminder(lang: Language, minutes: u32) -> String {
-    get_strings(lang).smart_reminder_body.replace("{}", &minutes.to_string())
+/// Format snooze menu item
+pub fn format_snooze(lang: Language, minutes: u64) -> String {
+    get_strings(lang).snooze.replace("{}", &minutes.to_string())
+}
+
+/// Format snoozed menu item with the local end time ("14:30")
+pub fn format_snoozed_until(lang: Language, until: &str) -> String {
+    get_strings(lang).snoozed_until.replace("{}", until)
+}
+
+/// Format a short duration such as "45s", "3m05s", "1h02m"
+pub fn format_duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Format today's response latency summary
+pub fn format_latency_summary(
+    lang: Language,
+    count: usize,
+    median_secs: u64,
+    p90_secs: u64,
+) -> String {
+    let s = get_strings(lang);
+    if count == 0 {
+        return s.latency_none.to_string();
+    }
+    s.latency_summary
+        .replacen("{}", &count.to_string(), 1)
+        .replacen("{}", &format_duration(median_secs), 1)
+        .replacen("{}", &format_duration(p90_secs), 1)
 }
 
 /// Format version string
 pub fn format_version(lang: Language, version: &str) -> String {
     get_strings(lang).version.replace("{}", version)
 }
-
-/// Format update available string
-pub fn format_update_available(lang: Language, version: &str) -> String {
-    get_strings(lang).update_available.replace("{}", version)
-}
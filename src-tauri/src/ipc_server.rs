@@ -1,14 +1,135 @@
+use crate::analytics;
+use crate::clock::SharedClock;
+use crate::history::HistoryStore;
+use crate::state_manager::{
+    aggregate_state, CliState, CliStatus, SessionSnapshot, StateChangeEvent,
+};
 use serde::{Deserialize, Serialize};
-use std::io::{BufRead, BufReader};
-use std::os::unix::net::UnixListener;
-use std::path::Path;
-use std::sync::mpsc::Sender;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-pub const SOCKET_PATH: &str = "/tmp/focus-guard.sock";
+/// 覆盖 socket 路径的环境变量
+pub const SOCKET_ENV: &str = "FOCUS_GUARD_SOCKET";
+const SOCKET_NAME: &str = "focus-guard.sock";
+
+/// 解析 socket 路径，scripts/focus-guard-notify 使用完全相同的查找顺序：
+/// 1. $FOCUS_GUARD_SOCKET
+/// 2. $XDG_RUNTIME_DIR/focus-guard.sock
+/// 3. /tmp/focus-guard-<uid>.sock
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(SOCKET_ENV).filter(|p| !p.is_empty()) {
+        return PathBuf::from(path);
+    }
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR").filter(|p| !p.is_empty()) {
+        return PathBuf::from(dir).join(SOCKET_NAME);
+    }
+    PathBuf::from(format!("/tmp/focus-guard-{}.sock", current_uid()))
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: getuid 没有失败情况
+    unsafe { libc::getuid() }
+}
+
+/// 创建只有当前用户可以读写的 socket
+/// 先在目标旁边建一个 0700 的临时目录，在里面 bind 并改为 0600，再 rename 到目标路径。
+/// socket 出现在目标路径时权限已经收紧，也不用修改整个进程的 umask
+fn bind_private(path: &Path) -> std::io::Result<UnixListener> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let dir = parent.join(format!(".focus-guard-{}.tmp", std::process::id()));
+    std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    let tmp = dir.join(SOCKET_NAME);
+    let result = UnixListener::bind(&tmp).and_then(|listener| {
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&tmp, path)?;
+        Ok(listener)
+    });
+    // rename 成功后目录已经是空的；失败时连同 socket 一起清理
+    let _ = std::fs::remove_file(&tmp);
+    let _ = std::fs::remove_dir(&dir);
+    result
+}
+
+/// 获取连接对端进程的 uid（Linux: SO_PEERCRED）
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: cred 和 len 指向有效内存，大小与 SO_PEERCRED 要求一致
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret == 0 {
+        Some(cred.uid)
+    } else {
+        None
+    }
+}
+
+/// 获取连接对端进程的 uid（macOS/BSD: getpeereid）
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    // SAFETY: uid 和 gid 指向有效内存
+    let ret = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+    if ret == 0 {
+        Some(uid)
+    } else {
+        None
+    }
+}
+
+/// 清理旧的 socket 文件，只删除属于当前用户的 socket
+fn remove_stale_socket(path: &Path) -> Result<(), String> {
+    let meta = match std::fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return Ok(()),
+    };
+    if !meta.file_type().is_socket() {
+        return Err(format!("{} exists and is not a socket", path.display()));
+    }
+    if meta.uid() != current_uid() {
+        return Err(format!(
+            "{} is owned by uid {}, refusing to replace it",
+            path.display(),
+            meta.uid()
+        ));
+    }
+    std::fs::remove_file(path).map_err(|e| e.to_string())
+}
+
+/// 当前协议版本（CliMessage 的 "v" 字段）
+pub const PROTOCOL_VERSION: u32 = 2;
+/// 仍然兼容的最低协议版本，没有 "v" 字段的旧消息视为 v1
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
 
 /// CLI 事件类型
+/// 未知事件名不会导致解析失败，而是保留为 Unknown，便于新旧版本共存
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
+#[serde(from = "String", into = "String")]
 pub enum CliEvent {
     SessionStart,
     SessionEnd,
@@ -16,11 +137,62 @@ pub enum CliEvent {
     Stop,       // Claude Stop, AfterAgent
     IdlePrompt, // Claude idle_prompt notification
     PermissionPrompt,
+    Unknown(String), // 新版 hook 发来的、本版本不认识的事件
+}
+
+impl CliEvent {
+    /// 本版本支持的事件名
+    pub const KNOWN: &'static [&'static str] = &[
+        "session_start",
+        "session_end",
+        "working",
+        "stop",
+        "idle_prompt",
+        "permission_prompt",
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            CliEvent::SessionStart => "session_start",
+            CliEvent::SessionEnd => "session_end",
+            CliEvent::Working => "working",
+            CliEvent::Stop => "stop",
+            CliEvent::IdlePrompt => "idle_prompt",
+            CliEvent::PermissionPrompt => "permission_prompt",
+            CliEvent::Unknown(name) => name,
+        }
+    }
+}
+
+impl From<String> for CliEvent {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "session_start" => CliEvent::SessionStart,
+            "session_end" => CliEvent::SessionEnd,
+            "working" => CliEvent::Working,
+            "stop" => CliEvent::Stop,
+            "idle_prompt" => CliEvent::IdlePrompt,
+            "permission_prompt" => CliEvent::PermissionPrompt,
+            _ => CliEvent::Unknown(name),
+        }
+    }
+}
+
+impl From<CliEvent> for String {
+    fn from(event: CliEvent) -> Self {
+        event.as_str().to_string()
+    }
+}
+
+fn default_protocol_version() -> u32 {
+    MIN_PROTOCOL_VERSION
 }
 
 /// 从 CLI hooks 接收的消息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CliMessage {
+    #[serde(default = "default_protocol_version")]
+    pub v: u32, // 协议版本
     pub cli: String, // "claude", "gemini", "codex"
     pub event: CliEvent,
     #[serde(default)]
@@ -33,16 +205,235 @@ pub struct CliMessage {
     pub cwd: Option<String>, // 工作目录
 }
 
+impl CliMessage {
+    /// CliMessage 支持的字段，hello 握手时告知客户端
+    pub const FIELDS: &'static [&'static str] =
+        &["v", "cli", "event", "pid", "timestamp", "session_id", "cwd"];
+
+    /// 构造当前协议版本的消息
+    pub fn new(cli: impl Into<String>, event: CliEvent) -> Self {
+        Self {
+            v: PROTOCOL_VERSION,
+            cli: cli.into(),
+            event,
+            pid: None,
+            timestamp: None,
+            session_id: None,
+            cwd: None,
+        }
+    }
+}
+
+/// 查询命令（请求/响应），如 {"cmd":"status"}
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum IpcCommand {
+    /// 握手：客户端报告自己的协议版本，服务端返回支持的事件和字段
+    Hello {
+        #[serde(default)]
+        v: Option<u32>,
+    },
+    Status,       // 聚合状态 + 久坐时间
+    ListSessions, // 所有会话详情
+    ResetTimer,   // 重置久坐计时
+    Subscribe,    // 保持连接，持续接收事件推送
+    /// 最近几天（默认 7 天）的响应延迟统计
+    Latency {
+        #[serde(default)]
+        days: Option<u32>,
+    },
+    /// 回应久坐提醒，如 {"cmd":"reminder","action":"took_break"}
+    Reminder {
+        action: ReminderAction,
+    },
+}
+
+/// 对久坐提醒的回应（通知按钮、托盘菜单和 IPC 共用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderAction {
+    /// 10 分钟后再提醒
+    Snooze,
+    /// 已经休息过了，立即重置计时
+    TookBreak,
+    /// 跳过这次，再坐满一个提醒间隔才提醒
+    Skip,
+}
+
+/// 查询命令的响应，每个请求对应一行 JSON
+#[derive(Debug, Serialize)]
+pub struct IpcResponse {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl IpcResponse {
+    fn ok(data: serde_json::Value) -> Self {
+        Self {
+            ok: true,
+            data: Some(data),
+            error: None,
+        }
+    }
+
+    fn error(msg: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            data: None,
+            error: Some(msg.into()),
+        }
+    }
+}
+
+/// status 命令的返回内容
+#[derive(Debug, Serialize)]
+pub struct StatusReply {
+    pub state: CliState,
+    pub sitting_minutes: u32,
+    pub sessions: usize,
+    pub active_sessions: usize,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub unknown_events: HashMap<String, u64>,
+}
+
+/// hello 命令的返回内容
+#[derive(Debug, Serialize)]
+pub struct HelloReply {
+    pub app_version: &'static str,
+    pub protocol_version: u32,
+    pub min_protocol_version: u32,
+    /// 双方都支持的版本，客户端应按这个版本发送消息
+    pub negotiated_version: u32,
+    pub events: &'static [&'static str],
+    pub fields: &'static [&'static str],
+    pub commands: &'static [&'static str],
+}
+
+/// 支持的查询命令
+const COMMANDS: &[&str] = &[
+    "hello",
+    "status",
+    "list_sessions",
+    "reset_timer",
+    "subscribe",
+    "latency",
+    "reminder",
+];
+
+/// 协议统计：记录收到的未知事件，避免静默丢弃
+#[derive(Default)]
+pub struct ProtocolStats {
+    unknown_events: Mutex<HashMap<String, u64>>,
+}
+
+impl ProtocolStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record_unknown(&self, name: &str) {
+        *self
+            .unknown_events
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert(0) += 1;
+    }
+
+    pub fn unknown_events(&self) -> HashMap<String, u64> {
+        self.unknown_events.lock().unwrap().clone()
+    }
+}
+
+/// 推送给订阅者的事件，每行一个 JSON
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IpcEvent {
+    StateChange(StateChangeEvent),
+    SittingTick { minutes: u32 },
+}
+
+/// 每个订阅者最多积压的事件数，超过后断开这个订阅者
+const SUBSCRIBER_QUEUE: usize = 256;
+/// 向订阅者写入一行的超时时间，客户端不读数据时不会一直卡住推送线程
+const SUBSCRIBER_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 订阅者列表，负责把事件分发给所有 subscribe 连接
+#[derive(Default)]
+pub struct Subscribers {
+    senders: Mutex<Vec<SyncSender<String>>>,
+}
+
+impl Subscribers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn subscribe(&self) -> Receiver<String> {
+        let (tx, rx) = mpsc::sync_channel(SUBSCRIBER_QUEUE);
+        self.senders.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// 广播事件，顺便移除已断开和积压过多的订阅者
+    pub fn broadcast(&self, event: &IpcEvent) {
+        let mut senders = self.senders.lock().unwrap();
+        if senders.is_empty() {
+            return;
+        }
+        let line = match serde_json::to_string(event) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("Failed to serialize IPC event: {}", e);
+                return;
+            }
+        };
+        senders.retain(|tx| match tx.try_send(line.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                eprintln!("IPC subscriber is not reading, dropping it");
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+}
+
+/// IPC 服务器处理查询命令时需要的共享状态
+#[derive(Clone)]
+pub struct IpcContext {
+    pub cli_states: Arc<Mutex<HashMap<String, CliStatus>>>,
+    pub sitting_minutes: Arc<Mutex<u32>>,
+    pub subscribers: Arc<Subscribers>,
+    pub protocol_stats: Arc<ProtocolStats>,
+    pub clock: SharedClock,
+    pub history: Arc<HistoryStore>,
+    /// 计时被重置后的回调（用于刷新托盘），参数为重置前的分钟数
+    pub on_timer_reset: Arc<dyn Fn(u32) + Send + Sync>,
+    /// 处理久坐提醒的回应，没有待回应的提醒时返回错误
+    pub on_reminder_action: Arc<dyn Fn(ReminderAction) -> Result<(), String> + Send + Sync>,
+    /// 校验 CLI 事件中的 PID
+    pub resolve_pid: PidResolver,
+}
+
+/// 参数为 cli、pid，返回真正的 CLI 进程，无效时返回 None
+pub type PidResolver = Arc<dyn Fn(&str, u32) -> Option<u32> + Send + Sync>;
+
 /// 启动 Unix Socket 服务器
-pub fn start_ipc_server(sender: Sender<CliMessage>) {
+pub fn start_ipc_server(sender: Sender<CliMessage>, context: IpcContext) {
+    let socket_path = socket_path();
+
     // 清理旧的 socket 文件
-    let socket_path = Path::new(SOCKET_PATH);
-    if socket_path.exists() {
-        let _ = std::fs::remove_file(socket_path);
+    if let Err(e) = remove_stale_socket(&socket_path) {
+        eprintln!("Failed to clean up Unix socket: {}", e);
+        return;
     }
 
     // 创建 Unix Socket 监听器
-    let listener = match UnixListener::bind(SOCKET_PATH) {
+    let listener = match bind_private(&socket_path) {
         Ok(l) => l,
         Err(e) => {
             eprintln!("Failed to bind Unix socket: {}", e);
@@ -50,15 +441,25 @@ pub fn start_ipc_server(sender: Sender<CliMessage>) {
         }
     };
 
-    println!("IPC server listening on {}", SOCKET_PATH);
+    println!("IPC server listening on {}", socket_path.display());
+
+    let owner_uid = current_uid();
 
     // 处理连接
     for stream in listener.incoming() {
         match stream {
             Ok(stream) => {
+                // 拒绝其他用户的连接，防止伪造事件
+                let uid = peer_uid(&stream);
+                if uid != Some(owner_uid) {
+                    eprintln!("Rejected IPC connection from uid {:?}", uid);
+                    continue;
+                }
+
                 let sender = sender.clone();
+                let context = context.clone();
                 std::thread::spawn(move || {
-                    handle_connection(stream, sender);
+                    handle_connection(stream, sender, context);
                 });
             }
             Err(e) => {
@@ -68,7 +469,14 @@ pub fn start_ipc_server(sender: Sender<CliMessage>) {
     }
 }
 
-fn handle_connection(stream: std::os::unix::net::UnixStream, sender: Sender<CliMessage>) {
+fn handle_connection(stream: UnixStream, sender: Sender<CliMessage>, context: IpcContext) {
+    let mut writer = match stream.try_clone() {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("Failed to clone IPC stream: {}", e);
+            return;
+        }
+    };
     let reader = BufReader::new(stream);
 
     for line in reader.lines() {
@@ -78,9 +486,47 @@ fn handle_connection(stream: std::os::unix::net::UnixStream, sender: Sender<CliM
                     continue;
                 }
 
+                // 带 cmd 字段的是查询命令，需要回写响应；其余按 CLI 事件处理
+                if is_command(&data) {
+                    let response = match serde_json::from_str::<IpcCommand>(&data) {
+                        Ok(IpcCommand::Subscribe) => {
+                            // 订阅模式：确认后把连接交给事件推送循环
+                            let events = context.subscribers.subscribe();
+                            let ack = IpcResponse::ok(serde_json::json!({ "subscribed": true }));
+                            if write_response(&mut writer, &ack).is_ok() {
+                                stream_events(&mut writer, events);
+                            }
+                            break;
+                        }
+                        Ok(cmd) => handle_command(cmd, &context),
+                        Err(e) => IpcResponse::error(format!("invalid command: {}", e)),
+                    };
+                    if write_response(&mut writer, &response).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+
                 match serde_json::from_str::<CliMessage>(&data) {
-                    Ok(msg) => {
+                    Ok(mut msg) => {
                         println!("Received CLI event: {:?}", msg);
+                        if let Some(pid) = msg.pid {
+                            msg.pid = (context.resolve_pid)(&msg.cli, pid);
+                            if msg.pid != Some(pid) {
+                                println!("CLI event PID {} resolved to {:?}", pid, msg.pid);
+                            }
+                        }
+                        if msg.v > PROTOCOL_VERSION {
+                            println!(
+                                "CLI message uses newer protocol v{} (supported: v{})",
+                                msg.v, PROTOCOL_VERSION
+                            );
+                        }
+                        // 未知事件照常转发（用于刷新会话活跃时间），并记录下来
+                        if let CliEvent::Unknown(name) = &msg.event {
+                            eprintln!("Unknown CLI event from {}: {}", msg.cli, name);
+                            context.protocol_stats.record_unknown(name);
+                        }
                         if sender.send(msg).is_err() {
                             eprintln!("Failed to send message to state manager");
                             break;
@@ -99,10 +545,411 @@ fn handle_connection(stream: std::os::unix::net::UnixStream, sender: Sender<CliM
     }
 }
 
+/// 判断一行数据是否为查询命令
+fn is_command(data: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(data)
+        .map(|v| v.get("cmd").is_some())
+        .unwrap_or(false)
+}
+
+/// 执行查询命令
+fn handle_command(cmd: IpcCommand, context: &IpcContext) -> IpcResponse {
+    match cmd {
+        IpcCommand::Hello { v } => {
+            let client_version = v.unwrap_or(MIN_PROTOCOL_VERSION);
+            if client_version < MIN_PROTOCOL_VERSION {
+                return IpcResponse::error(format!(
+                    "protocol v{} is no longer supported (minimum: v{})",
+                    client_version, MIN_PROTOCOL_VERSION
+                ));
+            }
+            to_response(&HelloReply {
+                app_version: env!("CARGO_PKG_VERSION"),
+                protocol_version: PROTOCOL_VERSION,
+                min_protocol_version: MIN_PROTOCOL_VERSION,
+                negotiated_version: client_version.min(PROTOCOL_VERSION),
+                events: CliEvent::KNOWN,
+                fields: CliMessage::FIELDS,
+                commands: COMMANDS,
+            })
+        }
+        IpcCommand::Status => {
+            let states = context.cli_states.lock().unwrap();
+            let reply = StatusReply {
+                state: aggregate_state(&states),
+                sitting_minutes: *context.sitting_minutes.lock().unwrap(),
+                sessions: states.len(),
+                active_sessions: states
+                    .values()
+                    .filter(|s| s.state != CliState::Offline)
+                    .count(),
+                unknown_events: context.protocol_stats.unknown_events(),
+            };
+            drop(states);
+            to_response(&reply)
+        }
+        IpcCommand::ListSessions => {
+            let now = context.clock.now();
+            let states = context.cli_states.lock().unwrap();
+            let mut sessions: Vec<SessionSnapshot> =
+                states.iter().map(|(key, s)| s.snapshot(key, now)).collect();
+            drop(states);
+            sessions.sort_by(|a, b| a.key.cmp(&b.key));
+            to_response(&sessions)
+        }
+        IpcCommand::ResetTimer => {
+            let previous = std::mem::replace(&mut *context.sitting_minutes.lock().unwrap(), 0);
+            println!("IPC: sitting timer reset");
+            (context.on_timer_reset)(previous);
+            IpcResponse::ok(serde_json::json!({ "sitting_minutes": 0 }))
+        }
+        IpcCommand::Latency { days } => {
+            let days = days.unwrap_or(7).clamp(1, 90);
+            let report =
+                analytics::recent_report(&context.history, context.clock.unix_secs(), days);
+            to_response(&report)
+        }
+        IpcCommand::Reminder { action } => match (context.on_reminder_action)(action) {
+            Ok(()) => IpcResponse::ok(serde_json::json!({
+                "sitting_minutes": *context.sitting_minutes.lock().unwrap()
+            })),
+            Err(e) => IpcResponse::error(e),
+        },
+        IpcCommand::Subscribe => IpcResponse::error("subscribe must be handled by the connection"),
+    }
+}
+
+/// 向订阅连接持续写入事件，直到客户端断开、写入超时或因积压过多被移除
+fn stream_events(writer: &mut UnixStream, events: Receiver<String>) {
+    if let Err(e) = writer.set_write_timeout(Some(SUBSCRIBER_WRITE_TIMEOUT)) {
+        eprintln!("Failed to set IPC write timeout: {}", e);
+        return;
+    }
+    for mut line in events {
+        line.push('\n');
+        if writer.write_all(line.as_bytes()).is_err() {
+            println!("IPC subscriber disconnected");
+            break;
+        }
+    }
+}
+
+fn to_response<T: Serialize>(value: &T) -> IpcResponse {
+    match serde_json::to_value(value) {
+        Ok(v) => IpcResponse::ok(v),
+        Err(e) => IpcResponse::error(e.to_string()),
+    }
+}
+
+/// 写回一行 JSON 响应
+fn write_response(writer: &mut UnixStream, response: &IpcResponse) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(response).map_err(std::io::Error::other)?;
+    line.push('\n');
+    if let Err(e) = writer.write_all(line.as_bytes()) {
+        eprintln!("Failed to write IPC response: {}", e);
+        return Err(e);
+    }
+    Ok(())
+}
+
 /// 清理 socket 文件
 pub fn cleanup() {
-    let socket_path = Path::new(SOCKET_PATH);
-    if socket_path.exists() {
-        let _ = std::fs::remove_file(socket_path);
+    let _ = remove_stale_socket(&socket_path());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    /// 每个测试使用独立的临时目录
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("focus-guard-ipc-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn context(name: &str) -> IpcContext {
+        IpcContext {
+            cli_states: Arc::new(Mutex::new(HashMap::new())),
+            sitting_minutes: Arc::new(Mutex::new(42)),
+            subscribers: Arc::new(Subscribers::new()),
+            protocol_stats: Arc::new(ProtocolStats::new()),
+            clock: Arc::new(ManualClock::new(1_700_000_000)),
+            history: Arc::new(HistoryStore::new(scratch_dir(name).join("history.jsonl"))),
+            on_timer_reset: Arc::new(|_| {}),
+            on_reminder_action: Arc::new(|action| match action {
+                ReminderAction::TookBreak => Ok(()),
+                _ => Err("no reminder is pending".to_string()),
+            }),
+            resolve_pid: Arc::new(|_, pid| Some(pid)),
+        }
+    }
+
+    /// 在后台线程里处理一条连接，返回客户端一端
+    fn connect(context: &IpcContext) -> (UnixStream, Receiver<CliMessage>) {
+        let (client, server) = UnixStream::pair().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let context = context.clone();
+        std::thread::spawn(move || handle_connection(server, sender, context));
+        (client, receiver)
+    }
+
+    fn read_json(reader: &mut BufReader<UnixStream>) -> serde_json::Value {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn state_change(cli: &str) -> IpcEvent {
+        IpcEvent::StateChange(StateChangeEvent {
+            state: CliState::WaitingInput,
+            pid: Some(7),
+            cwd: None,
+            cli_name: cli.to_string(),
+            state_changed: true,
+        })
+    }
+
+    fn run(context: &IpcContext, json: &str) -> serde_json::Value {
+        let cmd = serde_json::from_str::<IpcCommand>(json).unwrap();
+        serde_json::to_value(handle_command(cmd, context)).unwrap()
+    }
+
+    fn waiting_session(context: &IpcContext, key: &str) {
+        let mut status = CliStatus::with_details("claude".to_string(), Some(key.to_string()), None);
+        status.state = CliState::WaitingInput;
+        context
+            .cli_states
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), status);
+    }
+
+    #[test]
+    fn lines_with_cmd_are_commands() {
+        assert!(is_command(r#"{"cmd":"status"}"#));
+        assert!(is_command(r#"{"cmd":"no_such_command"}"#));
+        assert!(!is_command(r#"{"cli":"claude","event":"stop"}"#));
+        assert!(!is_command("not json"));
+        assert!(!is_command(r#"["cmd"]"#));
+    }
+
+    #[test]
+    fn connection_answers_commands_and_forwards_events() {
+        let context = context("routing");
+        let (mut client, events) = connect(&context);
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+
+        client
+            .write_all(b"{\"cli\":\"claude\",\"event\":\"stop\",\"pid\":7}\n")
+            .unwrap();
+        let msg = events.recv().unwrap();
+        assert_eq!((msg.cli.as_str(), &msg.event), ("claude", &CliEvent::Stop));
+        assert_eq!(msg.pid, Some(7));
+
+        client.write_all(b"{\"cmd\":\"status\"}\n").unwrap();
+        assert_eq!(read_json(&mut reader)["ok"], true);
+
+        client
+            .write_all(b"{\"cmd\":\"no_such_command\"}\n")
+            .unwrap();
+        let reply = read_json(&mut reader);
+        assert_eq!(reply["ok"], false);
+        assert!(reply["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid command"));
+    }
+
+    #[test]
+    fn status_reports_aggregate_state_and_sitting_time() {
+        let context = context("status");
+        waiting_session(&context, "claude:a");
+        context
+            .cli_states
+            .lock()
+            .unwrap()
+            .insert("codex".to_string(), CliStatus::new("codex".to_string()));
+
+        assert_eq!(
+            run(&context, r#"{"cmd":"status"}"#),
+            serde_json::json!({
+                "ok": true,
+                "data": {
+                    "state": "WaitingInput",
+                    "sitting_minutes": 42,
+                    "sessions": 2,
+                    "active_sessions": 1,
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn list_sessions_is_sorted_by_key() {
+        let context = context("list");
+        waiting_session(&context, "claude:b");
+        waiting_session(&context, "claude:a");
+
+        let reply = run(&context, r#"{"cmd":"list_sessions"}"#);
+        let keys: Vec<&str> = reply["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["key"].as_str().unwrap())
+            .collect();
+        assert_eq!(keys, ["claude:a", "claude:b"]);
+        assert_eq!(reply["data"][0]["state"], "WaitingInput");
+    }
+
+    #[test]
+    fn reset_timer_zeroes_minutes_and_reports_previous() {
+        let previous = Arc::new(Mutex::new(None));
+        let mut context = context("reset");
+        let seen = previous.clone();
+        context.on_timer_reset = Arc::new(move |minutes| *seen.lock().unwrap() = Some(minutes));
+
+        assert_eq!(
+            run(&context, r#"{"cmd":"reset_timer"}"#),
+            serde_json::json!({ "ok": true, "data": { "sitting_minutes": 0 } })
+        );
+        assert_eq!(*context.sitting_minutes.lock().unwrap(), 0);
+        assert_eq!(*previous.lock().unwrap(), Some(42));
+    }
+
+    #[test]
+    fn latency_without_history_is_empty() {
+        let context = context("latency");
+        assert_eq!(
+            run(&context, r#"{"cmd":"latency","days":3}"#),
+            serde_json::json!({ "ok": true, "data": { "days": [] } })
+        );
+    }
+
+    #[test]
+    fn reminder_action_reports_errors() {
+        let context = context("reminder");
+        assert_eq!(
+            run(&context, r#"{"cmd":"reminder","action":"took_break"}"#),
+            serde_json::json!({ "ok": true, "data": { "sitting_minutes": 42 } })
+        );
+        assert_eq!(
+            run(&context, r#"{"cmd":"reminder","action":"snooze"}"#),
+            serde_json::json!({ "ok": false, "error": "no reminder is pending" })
+        );
+        assert!(
+            serde_json::from_str::<IpcCommand>(r#"{"cmd":"reminder","action":"later"}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn hello_negotiates_the_lower_version() {
+        let context = context("hello");
+        let reply = run(&context, r#"{"cmd":"hello","v":2}"#);
+        assert_eq!(reply["ok"], true);
+        assert_eq!(reply["data"]["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(reply["data"]["min_protocol_version"], MIN_PROTOCOL_VERSION);
+        assert_eq!(reply["data"]["negotiated_version"], 2);
+        assert_eq!(reply["data"]["events"], serde_json::json!(CliEvent::KNOWN));
+        assert_eq!(reply["data"]["commands"], serde_json::json!(COMMANDS));
+
+        // 旧客户端不带版本号时按 v1 处理，新客户端降到服务端的版本
+        let old = run(&context, r#"{"cmd":"hello"}"#);
+        assert_eq!(old["data"]["negotiated_version"], 1);
+        let newer = run(&context, r#"{"cmd":"hello","v":99}"#);
+        assert_eq!(newer["data"]["negotiated_version"], PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn hello_rejects_unsupported_versions() {
+        let context = context("hello_old");
+        let reply = run(&context, r#"{"cmd":"hello","v":0}"#);
+        assert_eq!(reply["ok"], false);
+        assert!(reply["error"].as_str().unwrap().contains("v0"));
+    }
+
+    #[test]
+    fn unknown_events_round_trip_and_are_counted() {
+        let msg: CliMessage =
+            serde_json::from_str(r#"{"cli":"claude","event":"compacting"}"#).unwrap();
+        assert_eq!(msg.event, CliEvent::Unknown("compacting".to_string()));
+        // 没有 "v" 字段的旧消息视为 v1
+        assert_eq!(msg.v, 1);
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["event"], "compacting");
+        assert_eq!(
+            serde_json::from_value::<CliMessage>(json).unwrap().event,
+            msg.event
+        );
+
+        // 未知事件照常转发，并出现在 status 的统计中
+        let context = context("unknown");
+        let (mut client, events) = connect(&context);
+        client
+            .write_all(b"{\"v\":3,\"cli\":\"claude\",\"event\":\"compacting\"}\n")
+            .unwrap();
+        assert_eq!(events.recv().unwrap().event, msg.event);
+        assert_eq!(
+            run(&context, r#"{"cmd":"status"}"#)["data"]["unknown_events"],
+            serde_json::json!({ "compacting": 1 })
+        );
+    }
+
+    #[test]
+    fn subscribe_is_only_valid_on_a_connection() {
+        let context = context("subscribe_cmd");
+        assert_eq!(run(&context, r#"{"cmd":"subscribe"}"#)["ok"], false);
+    }
+
+    #[test]
+    fn subscriber_receives_state_changes() {
+        let context = context("subscribe");
+        let (mut client, _) = connect(&context);
+        client.write_all(b"{\"cmd\":\"subscribe\"}\n").unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        assert_eq!(
+            read_json(&mut reader),
+            serde_json::json!({ "ok": true, "data": { "subscribed": true } })
+        );
+
+        context.subscribers.broadcast(&state_change("claude"));
+        let event = read_json(&mut reader);
+        assert_eq!(event["type"], "state_change");
+        assert_eq!(event["cli_name"], "claude");
+        assert_eq!(event["state"], "WaitingInput");
+    }
+
+    #[test]
+    fn subscriber_that_stops_reading_is_dropped() {
+        let subscribers = Subscribers::new();
+        let events = subscribers.subscribe();
+        for _ in 0..SUBSCRIBER_QUEUE {
+            subscribers.broadcast(&state_change("claude"));
+        }
+        assert_eq!(subscribers.senders.lock().unwrap().len(), 1);
+
+        subscribers.broadcast(&state_change("claude"));
+        assert!(subscribers.senders.lock().unwrap().is_empty());
+        // 已经排队的事件仍然可以读完
+        assert_eq!(events.iter().count(), SUBSCRIBER_QUEUE);
+    }
+
+    #[test]
+    fn socket_is_private_and_leaves_no_temp_dir() {
+        let dir = scratch_dir("bind");
+        let path = dir.join("test.sock");
+        let _listener = bind_private(&path).unwrap();
+
+        let meta = std::fs::symlink_metadata(&path).unwrap();
+        assert!(meta.file_type().is_socket());
+        assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+        assert!(UnixStream::connect(&path).is_ok());
+
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
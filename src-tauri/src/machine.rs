@@ -0,0 +1,472 @@
+//! CLI 会话状态机
+//!
+//! 不做任何 IO、不读取系统时间：输入一个事件和当前时间，
+//! 更新会话表并返回需要执行的副作用。StateManager 线程只负责驱动它。
+
+use crate::ipc_server::{CliEvent, CliMessage};
+use crate::state_manager::{
+    aggregate_state, make_state_key, CliState, CliStatus, StateChangeEvent,
+};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 状态机输入
+#[derive(Debug, Clone)]
+pub enum Input {
+    /// 来自 hooks 或兜底检测的消息
+    Message(CliMessage),
+    /// 定时检查（Stop 延迟、Idle 升级、卡住检测、清理下线会话）
+    Tick,
+}
+
+/// 状态机输出的副作用
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// 单个会话的状态发生变化
+    Transition {
+        key: String,
+        cli: String,
+        session_id: Option<String>,
+        cwd: Option<String>,
+        from: CliState,
+        to: CliState,
+    },
+    /// 通知前端（刷新菜单；state_changed 为 true 时需要通知/置顶）
+    Notify(StateChangeEvent),
+}
+
+/// 状态机用到的时间参数
+#[derive(Debug, Clone, Copy)]
+pub struct Timings {
+    /// 收到 Stop 后多久没有新事件视为等待输入
+    pub stop_delay: Duration,
+    /// 等待输入多久后升级为 Idle
+    pub idle_escalation: Duration,
+}
+
+impl Timings {
+    pub fn from_secs(stop_delay_secs: u64, idle_escalation_secs: u64) -> Self {
+        Self {
+            stop_delay: Duration::from_secs(stop_delay_secs),
+            idle_escalation: Duration::from_secs(idle_escalation_secs),
+        }
+    }
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self::from_secs(3, 60)
+    }
+}
+
+/// 全局时间参数和按 CLI 名称（小写）的覆盖
+#[derive(Debug, Clone, Default)]
+pub struct TimingTable {
+    pub default: Timings,
+    pub overrides: HashMap<String, Timings>,
+    /// Working 会话多久没有 hook 事件、也没有 CPU 占用视为卡住（None 不检测）
+    pub stall_after: Option<Duration>,
+    /// 下线的会话保留多久后从会话表中删除（None 不删除）
+    pub offline_retention: Option<Duration>,
+}
+
+impl TimingTable {
+    pub fn for_cli(&self, cli: &str) -> Timings {
+        self.overrides
+            .get(&cli.to_lowercase())
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// 状态机，会话表由调用方持有（与 IPC 查询共享）
+pub struct Machine {
+    timings: TimingTable,
+    last_aggregate: CliState,
+}
+
+impl Machine {
+    pub fn new(timings: TimingTable) -> Self {
+        Self {
+            timings,
+            last_aggregate: CliState::Offline,
+        }
+    }
+
+    /// 处理一个输入，返回需要执行的副作用
+    pub fn step(
+        &mut self,
+        sessions: &mut HashMap<String, CliStatus>,
+        input: Input,
+        now: Instant,
+    ) -> Vec<Effect> {
+        match input {
+            Input::Message(msg) => self.on_message(sessions, msg, now),
+            Input::Tick => self.on_tick(sessions, now),
+        }
+    }
+
+    fn on_message(
+        &mut self,
+        sessions: &mut HashMap<String, CliStatus>,
+        msg: CliMessage,
+        now: Instant,
+    ) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        let key = make_state_key(&msg.cli, msg.session_id.as_deref());
+        let status = sessions.entry(key.clone()).or_insert_with(|| {
+            CliStatus::with_details(msg.cli.clone(), msg.session_id.clone(), msg.cwd.clone())
+        });
+
+        status.last_event = Some(msg.event.clone());
+        status.last_update = now;
+
+        // 更新 pid、session_id 和 cwd（如果有新值；无效的 PID 已由 IPC 服务器去掉）
+        if msg.pid.is_some() {
+            status.pid = msg.pid;
+        }
+        if msg.session_id.is_some() {
+            status.session_id = msg.session_id.clone();
+        }
+        if msg.cwd.is_some() {
+            status.cwd = msg.cwd.clone();
+            status.update_display_name();
+        }
+
+        // 处理 Stop 事件的延迟逻辑
+        let new_state = match msg.event {
+            CliEvent::SessionStart => {
+                status.stop_received_at = None;
+                CliState::Working
+            }
+            CliEvent::SessionEnd => {
+                status.stop_received_at = None;
+                CliState::Offline
+            }
+            CliEvent::Working => {
+                // 收到 Working 事件，清除 Stop 记录
+                status.stop_received_at = None;
+                CliState::Working
+            }
+            CliEvent::Stop => {
+                // Stop 事件：记录时间，但保持当前状态，延迟判断是否真的需要用户输入
+                status.stop_received_at = Some(now);
+                keep_or_working(status.state)
+            }
+            CliEvent::IdlePrompt => {
+                status.stop_received_at = None;
+                CliState::Idle
+            }
+            CliEvent::PermissionPrompt => {
+                // 权限提示需要立即响应
+                status.stop_received_at = None;
+                CliState::WaitingInput
+            }
+            // 未知事件只刷新活跃时间，不改变状态
+            CliEvent::Unknown(_) => keep_or_working(status.state),
+        };
+
+        if new_state != status.state {
+            effects.push(Effect::Transition {
+                key,
+                cli: status.cli_name.clone(),
+                session_id: status.session_id.clone(),
+                cwd: status.cwd.clone(),
+                from: status.state,
+                to: new_state,
+            });
+        }
+        status.state = new_state;
+
+        let pid = status.pid;
+        let cwd = status.cwd.clone();
+        let cli_name = status.cli_name.clone();
+
+        // 每次收到事件都通知（用于更新菜单），但标记是否需要通知/置顶
+        let state_changed = self.update_aggregate(sessions);
+        effects.push(Effect::Notify(StateChangeEvent {
+            state: self.last_aggregate,
+            pid,
+            cwd,
+            cli_name,
+            state_changed,
+        }));
+        effects
+    }
+
+    fn on_tick(&mut self, sessions: &mut HashMap<String, CliStatus>, now: Instant) -> Vec<Effect> {
+        let mut effects = Vec::new();
+
+        for (key, status) in sessions.iter_mut() {
+            let from = status.state;
+            let timings = self.timings.for_cli(&status.cli_name);
+
+            // 检查 Stop 延迟：收到 Stop 后超过延迟时间没有新事件，转为 WaitingInput
+            if let Some(stop_time) = status.stop_received_at {
+                if now.saturating_duration_since(stop_time) > timings.stop_delay {
+                    status.state = CliState::WaitingInput;
+                    status.stop_received_at = None;
+                }
+            }
+
+            // 将长时间 WaitingInput 的状态转为 Idle
+            if status.state == CliState::WaitingInput
+                && now.saturating_duration_since(status.last_update) > timings.idle_escalation
+            {
+                status.state = CliState::Idle;
+            }
+
+            // 卡住检测：只针对 hooks 会话（兜底会话没有事件，无法区分卡住和等待输入），
+            // 且要有资源采样（找不到进程时交给兜底检测处理退出）
+            if let Some(stall_after) = self.timings.stall_after {
+                let quiet_since = match status.last_cpu_activity {
+                    Some(active) => active.max(status.last_update),
+                    None => status.last_update,
+                };
+                let quiet = now.saturating_duration_since(quiet_since) > stall_after;
+                match status.state {
+                    CliState::Working
+                        if quiet && !status.is_fallback() && status.resources.is_some() =>
+                    {
+                        status.state = CliState::Stalled;
+                    }
+                    // CPU 又有占用了，恢复为 Working
+                    CliState::Stalled if !quiet => status.state = CliState::Working,
+                    _ => {}
+                }
+            }
+
+            if status.state != from {
+                effects.push(Effect::Transition {
+                    key: key.clone(),
+                    cli: status.cli_name.clone(),
+                    session_id: status.session_id.clone(),
+                    cwd: status.cwd.clone(),
+                    from,
+                    to: status.state,
+                });
+            }
+        }
+
+        // 删除下线太久的会话，SessionEnd 没到、由兜底检测结束的会话也会在这里清理
+        if let Some(retention) = self.timings.offline_retention {
+            sessions.retain(|_, status| {
+                status.state != CliState::Offline
+                    || now.saturating_duration_since(status.last_update) <= retention
+            });
+        }
+
+        // 聚合状态变化或有会话变化时才通知
+        let state_changed = self.update_aggregate(sessions);
+        if state_changed || !effects.is_empty() {
+            effects.push(Effect::Notify(StateChangeEvent {
+                state: self.last_aggregate,
+                pid: None,
+                cwd: None,
+                cli_name: String::new(),
+                state_changed,
+            }));
+        }
+        effects
+    }
+
+    /// 重新计算聚合状态，返回是否变化
+    fn update_aggregate(&mut self, sessions: &HashMap<String, CliStatus>) -> bool {
+        let aggregate = aggregate_state(sessions);
+        let changed = aggregate != self.last_aggregate;
+        self.last_aggregate = aggregate;
+        changed
+    }
+}
+
+/// Offline 的会话收到事件说明已经在运行，Stalled 的会话收到事件说明没有卡住，其余保持不变
+fn keep_or_working(state: CliState) -> CliState {
+    match state {
+        CliState::Offline | CliState::Stalled => CliState::Working,
+        _ => state,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use crate::resource_monitor::ResourceUsage;
+
+    /// 用手动时钟驱动状态机
+    struct Harness {
+        clock: ManualClock,
+        machine: Machine,
+        sessions: HashMap<String, CliStatus>,
+    }
+
+    impl Harness {
+        fn new(timings: TimingTable) -> Self {
+            Self {
+                clock: ManualClock::new(1_700_000_000),
+                machine: Machine::new(timings),
+                sessions: HashMap::new(),
+            }
+        }
+
+        fn send(&mut self, cli: &str, session: &str, event: CliEvent) -> Vec<Effect> {
+            let mut msg = CliMessage::new(cli, event);
+            msg.session_id = Some(session.to_string());
+            let now = self.clock.now();
+            self.machine
+                .step(&mut self.sessions, Input::Message(msg), now)
+        }
+
+        /// 拨动时间后执行一次定时检查
+        fn tick_after(&mut self, secs: u64) -> Vec<Effect> {
+            self.clock.advance(Duration::from_secs(secs));
+            let now = self.clock.now();
+            self.machine.step(&mut self.sessions, Input::Tick, now)
+        }
+
+        fn state(&self, cli: &str, session: &str) -> Option<CliState> {
+            self.sessions
+                .get(&make_state_key(cli, Some(session)))
+                .map(|s| s.state)
+        }
+    }
+
+    fn transitions(effects: &[Effect]) -> Vec<(CliState, CliState)> {
+        effects
+            .iter()
+            .filter_map(|e| match e {
+                Effect::Transition { from, to, .. } => Some((*from, *to)),
+                Effect::Notify(_) => None,
+            })
+            .collect()
+    }
+
+    fn aggregate_changed(effects: &[Effect]) -> Option<CliState> {
+        effects.iter().find_map(|e| match e {
+            Effect::Notify(event) if event.state_changed => Some(event.state),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn stop_waits_for_delay_before_waiting_input() {
+        let mut h = Harness::new(TimingTable::default());
+        h.send("claude", "a", CliEvent::SessionStart);
+        assert!(transitions(&h.send("claude", "a", CliEvent::Stop)).is_empty());
+
+        assert!(h.tick_after(2).is_empty());
+        assert_eq!(h.state("claude", "a"), Some(CliState::Working));
+
+        let effects = h.tick_after(2);
+        assert_eq!(
+            transitions(&effects),
+            [(CliState::Working, CliState::WaitingInput)]
+        );
+        assert_eq!(aggregate_changed(&effects), Some(CliState::WaitingInput));
+
+        let effects = h.send("claude", "a", CliEvent::Working);
+        assert_eq!(
+            transitions(&effects),
+            [(CliState::WaitingInput, CliState::Working)]
+        );
+        assert_eq!(aggregate_changed(&effects), Some(CliState::Working));
+    }
+
+    #[test]
+    fn event_within_stop_delay_keeps_working() {
+        let mut h = Harness::new(TimingTable::default());
+        h.send("claude", "a", CliEvent::SessionStart);
+        h.send("claude", "a", CliEvent::Stop);
+        h.tick_after(2);
+        h.send("claude", "a", CliEvent::Working);
+
+        assert!(h.tick_after(10).is_empty());
+        assert_eq!(h.state("claude", "a"), Some(CliState::Working));
+    }
+
+    #[test]
+    fn waiting_input_escalates_to_idle() {
+        let mut h = Harness::new(TimingTable::default());
+        h.send("claude", "a", CliEvent::PermissionPrompt);
+        assert_eq!(h.state("claude", "a"), Some(CliState::WaitingInput));
+
+        assert!(h.tick_after(60).is_empty());
+        let effects = h.tick_after(1);
+        assert_eq!(
+            transitions(&effects),
+            [(CliState::WaitingInput, CliState::Idle)]
+        );
+        assert_eq!(aggregate_changed(&effects), Some(CliState::Idle));
+    }
+
+    #[test]
+    fn per_cli_overrides_apply() {
+        let mut timings = TimingTable::default();
+        timings
+            .overrides
+            .insert("gemini".to_string(), Timings::from_secs(8, 60));
+        let mut h = Harness::new(timings);
+        h.send("claude", "a", CliEvent::SessionStart);
+        h.send("gemini", "b", CliEvent::SessionStart);
+        h.send("claude", "a", CliEvent::Stop);
+        h.send("gemini", "b", CliEvent::Stop);
+
+        h.tick_after(4);
+        assert_eq!(h.state("claude", "a"), Some(CliState::WaitingInput));
+        assert_eq!(h.state("gemini", "b"), Some(CliState::Working));
+
+        h.tick_after(4);
+        assert_eq!(h.state("gemini", "b"), Some(CliState::Working));
+
+        h.tick_after(1);
+        assert_eq!(h.state("gemini", "b"), Some(CliState::WaitingInput));
+    }
+
+    #[test]
+    fn quiet_working_session_stalls_and_recovers() {
+        let mut h = Harness::new(TimingTable {
+            stall_after: Some(Duration::from_secs(30)),
+            ..TimingTable::default()
+        });
+        h.send("claude", "a", CliEvent::SessionStart);
+
+        // 没有资源采样时不判断卡住
+        assert!(h.tick_after(31).is_empty());
+
+        let key = make_state_key("claude", Some("a"));
+        h.sessions.get_mut(&key).unwrap().resources = Some(ResourceUsage::default());
+        let effects = h.tick_after(1);
+        assert_eq!(
+            transitions(&effects),
+            [(CliState::Working, CliState::Stalled)]
+        );
+        assert_eq!(aggregate_changed(&effects), Some(CliState::Stalled));
+
+        // 又采样到 CPU 占用
+        h.sessions.get_mut(&key).unwrap().last_cpu_activity = Some(h.clock.now());
+        let effects = h.tick_after(1);
+        assert_eq!(
+            transitions(&effects),
+            [(CliState::Stalled, CliState::Working)]
+        );
+    }
+
+    #[test]
+    fn offline_sessions_are_pruned_after_retention() {
+        let mut h = Harness::new(TimingTable {
+            offline_retention: Some(Duration::from_secs(60)),
+            ..TimingTable::default()
+        });
+        h.send("claude", "a", CliEvent::SessionStart);
+        h.send("claude", "b", CliEvent::SessionStart);
+        h.send("claude", "a", CliEvent::SessionEnd);
+
+        h.tick_after(60);
+        assert_eq!(h.state("claude", "a"), Some(CliState::Offline));
+
+        h.tick_after(1);
+        assert_eq!(h.state("claude", "a"), None);
+        // 仍在运行的会话不受影响
+        assert_eq!(h.state("claude", "b"), Some(CliState::Working));
+    }
+}
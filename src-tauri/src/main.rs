@@ -1,5 +1,19 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
-    focus_guard_lib::run()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // focus-guard hooks install|uninstall|status ...
+    if args.first().map(String::as_str) == Some("hooks") {
+        std::process::exit(focus_guard_lib::hooks::run(&args[1..]));
+    }
+
+    // 无界面模式：不创建托盘，只通过 socket 和通知提供状态
+    // 没有启用 tray feature 时总是以无界面模式运行
+    if cfg!(not(feature = "tray")) || args.iter().any(|a| a == "--headless") {
+        focus_guard_lib::run_headless();
+    } else {
+        #[cfg(feature = "tray")]
+        focus_guard_lib::run();
+    }
 }
@@ -1,18 +1,143 @@
-use crate::i18n::{format_sitting_reminder, format_smart_reminder, get_strings, Language};
 use std::process::Command;
-use tauri_plugin_notification::NotificationExt;
+use std::time::Duration;
+#[cfg(feature = "tray")]
+use {
+    crate::channels::{Notification, NotificationChannel},
+    crate::i18n::{format_sitting_reminder, get_strings, Language},
+    tauri_plugin_notification::NotificationExt,
+};
 
 /// 播放系统提示音
-fn play_system_sound() {
-    // 使用 afplay 播放系统声音，这在 macOS 上更可靠
+pub fn play_system_sound() {
     std::thread::spawn(|| {
-        let _ = Command::new("afplay")
-            .arg("/System/Library/Sounds/Glass.aiff")
-            .output();
+        if cfg!(target_os = "macos") {
+            // 使用 afplay 播放系统声音，这在 macOS 上更可靠
+            let _ = Command::new("afplay")
+                .arg("/System/Library/Sounds/Glass.aiff")
+                .output();
+        } else {
+            let _ = Command::new("canberra-gtk-play")
+                .args(["-i", "message-new-instant"])
+                .output();
+        }
     });
 }
 
+/// 不依赖 Tauri 发送桌面通知（无界面模式使用）
+/// macOS 使用 osascript，其他平台使用 notify-send
+pub fn send_desktop_notification(title: &str, body: &str, with_sound: bool) -> Result<(), String> {
+    let output = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        );
+        Command::new("osascript").arg("-e").arg(script).output()
+    } else {
+        Command::new("notify-send")
+            .arg("--app-name=Focus Guard")
+            .arg(title)
+            .arg(body)
+            .output()
+    };
+
+    if with_sound {
+        play_system_sound();
+    }
+
+    let result = match output {
+        Ok(o) if o.status.success() => Ok(()),
+        Ok(o) => Err(String::from_utf8_lossy(&o.stderr).trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match &result {
+        Ok(_) => println!("Notification sent: {} - {}", title, body),
+        Err(e) => println!("Notification failed: {}", e),
+    }
+    result
+}
+
+/// 显示带按钮的桌面通知并等待用户选择，actions 为 (id, 按钮文字)，返回所选按钮的下标
+/// 关闭、超时或点击通知本身时返回 Ok(None)；系统不支持时返回 Err，由调用方改发普通通知
+/// Linux 使用 notify-send -A（libnotify 0.7.9+），超时后通知自动消失
+/// macOS 使用通知自带的按钮：最后一项作为关闭按钮，其余放在下拉菜单里；
+/// 系统不提供超时，用户处理通知前调用线程会一直等待
+pub fn ask_desktop_action(
+    title: &str,
+    body: &str,
+    actions: &[(&str, &str)],
+    timeout: Duration,
+) -> Result<Option<usize>, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = timeout;
+        ask_macos_action(title, body, actions)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let mut command = Command::new("notify-send");
+        command
+            .arg("--app-name=Focus Guard")
+            .arg("--wait")
+            .arg(format!("--expire-time={}", timeout.as_millis()));
+        for (id, label) in actions {
+            command.arg(format!("--action={}={}", id, label));
+        }
+        let output = command
+            .arg(title)
+            .arg(body)
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        // 输出所选按钮的 id，关闭或超时时没有输出
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(actions.iter().position(|(id, _)| *id == stdout.trim()))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn ask_macos_action(
+    title: &str,
+    body: &str,
+    actions: &[(&str, &str)],
+) -> Result<Option<usize>, String> {
+    use mac_notification_sys::{MainButton, NotificationResponse};
+
+    let Some(((_, close_label), rest)) = actions.split_last() else {
+        return Err("no actions".to_string());
+    };
+    let labels: Vec<&str> = rest.iter().map(|(_, label)| *label).collect();
+    let Some(&first) = labels.first() else {
+        return Err("no dropdown actions".to_string());
+    };
+    // 托盘模式下通知插件可能已经设置过，忽略 AlreadySet
+    let _ = mac_notification_sys::set_application(
+        &mac_notification_sys::get_bundle_identifier_or_default("Focus Guard"),
+    );
+    let response = mac_notification_sys::Notification::new()
+        .title(title)
+        .message(body)
+        .main_button(MainButton::DropdownActions(first, &labels))
+        .close_button(close_label)
+        .wait_for_click(true)
+        .send()
+        .map_err(|e| e.to_string())?;
+    Ok(match response {
+        NotificationResponse::ActionButton(label) => labels.iter().position(|l| *l == label),
+        NotificationResponse::CloseButton(_) => Some(actions.len() - 1),
+        _ => None,
+    })
+}
+
+/// 转换为 AppleScript 字符串字面量
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
 /// 发送系统通知（带声音选项）
+#[cfg(feature = "tray")]
 pub fn send_system_notification(
     app: &tauri::AppHandle,
     title: &str,
@@ -37,17 +162,21 @@ pub fn send_system_notification(
     result
 }
 
-/// 发送CLI等待通知
-pub fn notify_cli_waiting(
-    app: &tauri::AppHandle,
-    lang: Language,
-    with_sound: bool,
-) -> Result<(), String> {
-    let s = get_strings(lang);
-    send_system_notification(app, s.app_name, s.cli_waiting, with_sound)
+/// 托盘模式的桌面通知渠道
+#[cfg(feature = "tray")]
+pub struct TauriChannel {
+    pub app: tauri::AppHandle,
+}
+
+#[cfg(feature = "tray")]
+impl NotificationChannel for TauriChannel {
+    fn send(&self, n: &Notification) -> Result<(), String> {
+        send_system_notification(&self.app, &n.title, &n.body, n.with_sound)
+    }
 }
 
 /// 发送久坐提醒
+#[cfg(feature = "tray")]
 #[allow(dead_code)]
 pub fn notify_sitting_reminder(
     app: &tauri::AppHandle,
@@ -60,25 +189,15 @@ pub fn notify_sitting_reminder(
     send_system_notification(app, s.sitting_reminder_title, &body, with_sound)
 }
 
-/// 发送智能久坐提醒（CLI交互时触发）
-pub fn notify_smart_sitting_reminder(
-    app: &tauri::AppHandle,
-    lang: Language,
-    minutes: u32,
-    with_sound: bool,
-) -> Result<(), String> {
-    let s = get_strings(lang);
-    let body = format_smart_reminder(lang, minutes);
-    send_system_notification(app, s.smart_reminder_title, &body, with_sound)
-}
-
 /// 发送声音通知已开启的提示
+#[cfg(feature = "tray")]
 pub fn notify_sound_enabled(app: &tauri::AppHandle, lang: Language) -> Result<(), String> {
     let s = get_strings(lang);
     send_system_notification(app, s.app_name, s.sound_enabled_msg, true)
 }
 
 /// 请求通知权限
+#[cfg(feature = "tray")]
 pub fn request_notification_permission(app: &tauri::AppHandle) -> Result<bool, String> {
     app.notification()
         .request_permission()
@@ -87,6 +206,7 @@ pub fn request_notification_permission(app: &tauri::AppHandle) -> Result<bool, S
 }
 
 /// 检查通知权限状态
+#[cfg(feature = "tray")]
 #[allow(dead_code)]
 pub fn check_notification_permission(app: &tauri::AppHandle) -> Result<bool, String> {
     app.notification()
@@ -0,0 +1,285 @@
+//! 进程信息查询：工作目录、命令行、父进程、沿父进程链查找 CLI
+//!
+//! Linux 直接读取 /proc，不再为每个进程启动 lsof/ps/pgrep；
+//! macOS 等其他平台仍然调用这些命令行工具。
+
+use std::process::Command;
+
+/// 单个进程的基本信息
+#[derive(Debug, Clone)]
+pub struct ProcessDetails {
+    /// 进程名（内核记录的 comm，可能被截断）
+    pub name: String,
+    /// 完整命令行，参数以空格连接；读不到时为进程名
+    pub cmdline: String,
+    /// 命令行参数（ps 后端按空白拆分，含空格的参数会被拆开）
+    pub argv: Vec<String>,
+    /// 父进程 PID
+    pub ppid: u32,
+}
+
+/// 进程信息查询后端
+pub trait ProcessInspector: Send + Sync {
+    /// 进程的工作目录
+    fn cwd(&self, pid: u32) -> Option<String>;
+    /// 进程名、命令行和父进程
+    fn details(&self, pid: u32) -> Option<ProcessDetails>;
+}
+
+/// 当前平台使用的后端
+pub fn inspector() -> &'static dyn ProcessInspector {
+    #[cfg(target_os = "linux")]
+    {
+        &ProcFs
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        &PsTools
+    }
+}
+
+/// 读取 /proc 的后端（Linux）
+#[cfg(target_os = "linux")]
+pub struct ProcFs;
+
+/// 解析 /proc/<pid>/stat 的内容，返回 (comm, ppid)
+/// comm 可能包含空格和括号，所以以最后一个 ')' 为界
+#[cfg(target_os = "linux")]
+fn parse_stat(stat: &str) -> Option<(String, u32)> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?.to_string();
+    // ')' 之后依次是 state、ppid
+    let mut fields = stat.get(close + 1..)?.split_whitespace();
+    let _state = fields.next()?;
+    let ppid = fields.next()?.parse().ok()?;
+    Some((comm, ppid))
+}
+
+#[cfg(target_os = "linux")]
+impl ProcessInspector for ProcFs {
+    fn cwd(&self, pid: u32) -> Option<String> {
+        let path = std::fs::read_link(format!("/proc/{}/cwd", pid)).ok()?;
+        Some(path.to_string_lossy().into_owned())
+    }
+
+    fn details(&self, pid: u32) -> Option<ProcessDetails> {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let (name, ppid) = parse_stat(&stat)?;
+        // cmdline 以 NUL 分隔参数，内核线程和僵尸进程为空
+        let argv: Vec<String> = std::fs::read(format!("/proc/{}/cmdline", pid))
+            .map(|raw| {
+                raw.split(|b| *b == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let cmdline = if argv.is_empty() {
+            name.clone()
+        } else {
+            argv.join(" ")
+        };
+        Some(ProcessDetails {
+            name,
+            cmdline,
+            argv,
+            ppid,
+        })
+    }
+}
+
+/// 调用 lsof/ps/pgrep 的后端（macOS 等）
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+pub struct PsTools;
+
+impl ProcessInspector for PsTools {
+    fn cwd(&self, pid: u32) -> Option<String> {
+        // -a -d cwd 只输出 cwd 这一项，-Fn 以 "n<路径>" 输出，路径中的空格不会被拆开
+        let output = Command::new("lsof")
+            .args(["-a", "-p", &pid.to_string(), "-d", "cwd", "-Fn"])
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix('n'))
+            .map(|path| path.to_string())
+    }
+
+    fn details(&self, pid: u32) -> Option<ProcessDetails> {
+        // 分开获取 args 和 ppid，避免 ps 输出被截断
+        // 注意：ps -o args=,ppid= 会截断长路径，所以需要分开调用
+
+        // 获取完整命令路径（使用 -ww 确保不截断）
+        let args_output = Command::new("ps")
+            .args(["-ww", "-p", &pid.to_string(), "-o", "args="])
+            .output()
+            .ok()?;
+        let cmdline = String::from_utf8_lossy(&args_output.stdout)
+            .trim()
+            .to_string();
+        if cmdline.is_empty() {
+            return None;
+        }
+
+        // 获取父进程 PID 和进程名
+        let output = Command::new("ps")
+            .args(["-p", &pid.to_string(), "-o", "ppid=,comm="])
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout.trim();
+        let (ppid, comm) = line.split_once(char::is_whitespace)?;
+        let ppid = ppid.trim().parse().ok()?;
+        let comm = comm.trim();
+        let name = comm.rsplit('/').next().unwrap_or(comm).to_string();
+        let argv = cmdline
+            .split_whitespace()
+            .map(|arg| arg.to_string())
+            .collect();
+
+        Some(ProcessDetails {
+            name,
+            cmdline,
+            argv,
+            ppid,
+        })
+    }
+}
+
+/// hook 命令经由这些进程启动，查找 CLI 时跳过
+const LAUNCHERS: &[&str] = &["sh", "bash", "zsh", "dash", "fish", "env", "timeout"];
+
+/// hook 客户端使用：从 pid 的父进程开始向上查找，跳过 shell 等启动器，
+/// 返回第一个其他进程（即运行 hook 的 CLI）。到达 init 或查不到时返回 None
+pub fn find_cli_ancestor(pid: u32) -> Option<u32> {
+    cli_ancestor(inspector(), pid)
+}
+
+fn cli_ancestor(inspector: &dyn ProcessInspector, pid: u32) -> Option<u32> {
+    let mut current = inspector.details(pid)?.ppid;
+    // 最多向上查找 10 层，防止循环
+    for _ in 0..10 {
+        if current <= 1 {
+            return None;
+        }
+        let details = inspector.details(current)?;
+        // 登录 shell 的进程名带 "-" 前缀；Linux 的 comm 最长 15 字节，
+        // 脚本版 focus-guard-notify 会被截断，所以按前缀跳过
+        let name = details.name.trim_start_matches('-');
+        if !LAUNCHERS.contains(&name) && !name.starts_with("focus-guard") {
+            return Some(current);
+        }
+        current = details.ppid;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// 按 pid 返回预设进程名和父进程的后端
+    struct FakeProcesses(HashMap<u32, (&'static str, u32)>);
+
+    impl FakeProcesses {
+        fn new(processes: &[(u32, &'static str, u32)]) -> Self {
+            Self(
+                processes
+                    .iter()
+                    .map(|&(pid, name, ppid)| (pid, (name, ppid)))
+                    .collect(),
+            )
+        }
+    }
+
+    impl ProcessInspector for FakeProcesses {
+        fn cwd(&self, _pid: u32) -> Option<String> {
+            None
+        }
+
+        fn details(&self, pid: u32) -> Option<ProcessDetails> {
+            let &(name, ppid) = self.0.get(&pid)?;
+            Some(ProcessDetails {
+                name: name.to_string(),
+                cmdline: name.to_string(),
+                argv: vec![name.to_string()],
+                ppid,
+            })
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn stat_comm_may_contain_spaces_and_parens() {
+        let stat = "4242 (tmux: server) S 1 4242 4242 0 -1 4194560 0";
+        assert_eq!(parse_stat(stat), Some(("tmux: server".to_string(), 1)));
+        let stat = "77 (a) b) R 12 77 77";
+        assert_eq!(parse_stat(stat), Some(("a) b".to_string(), 12)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn stat_comm_is_truncated_by_the_kernel() {
+        // 内核只保留前 15 字节，focus-guard-notify 变成 focus-guard-not
+        let stat = "1000 (focus-guard-not) S 999 1000 1000 0";
+        assert_eq!(parse_stat(stat), Some(("focus-guard-not".to_string(), 999)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn malformed_stat_is_rejected() {
+        assert_eq!(parse_stat(""), None);
+        assert_eq!(parse_stat("12 (bash S 1"), None);
+        assert_eq!(parse_stat("12 (bash) S"), None);
+        assert_eq!(parse_stat("12 (bash) S parent"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn proc_fs_reads_this_process() {
+        let details = ProcFs.details(std::process::id()).unwrap();
+        assert_eq!(details.ppid, std::os::unix::process::parent_id());
+        assert!(!details.argv.is_empty());
+        assert_eq!(details.cmdline, details.argv.join(" "));
+    }
+
+    #[test]
+    fn ancestor_skips_shells_and_the_hook_client() {
+        let processes = FakeProcesses::new(&[
+            (1, "systemd", 0),
+            (10, "tmux: server", 1),
+            (20, "-bash", 10),
+            (30, "node", 20),
+            (40, "sh", 30),
+            (50, "env", 40),
+            // 内核截断后的 focus-guard-notify 脚本
+            (60, "focus-guard-no", 50),
+            (70, "focus-guard-not", 60),
+        ]);
+        assert_eq!(cli_ancestor(&processes, 70), Some(30));
+        // CLI 直接启动 hook 客户端
+        assert_eq!(cli_ancestor(&processes, 40), Some(30));
+    }
+
+    #[test]
+    fn login_shell_under_init_has_no_cli() {
+        let processes = FakeProcesses::new(&[
+            (1, "launchd", 0),
+            (5, "-zsh", 1),
+            (6, "bash", 5),
+            (7, "focus-guard-not", 6),
+        ]);
+        assert_eq!(cli_ancestor(&processes, 7), None);
+    }
+
+    #[test]
+    fn missing_parent_or_loop_gives_up() {
+        let missing = FakeProcesses::new(&[(9, "bash", 8)]);
+        assert_eq!(cli_ancestor(&missing, 9), None);
+        let looped = FakeProcesses::new(&[(2, "sh", 3), (3, "bash", 2)]);
+        assert_eq!(cli_ancestor(&looped, 2), None);
+    }
+}
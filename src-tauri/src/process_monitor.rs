@@ -1,5 +1,8 @@
+use crate::cli_registry::{script_target, CliRegistry};
+use crate::process_inspector::inspector;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use sysinfo::System;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,77 +11,139 @@ pub struct ProcessInfo {
     pub pid: u32,
     pub status: String,
     pub cwd: Option<String>,
+    pub cli_id: String, // 匹配到的 CLI（注册表中的 id）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpreter: Option<String>, // 由解释器运行时的解释器进程名（node、python3 等）
 }
 
-/// 获取进程的工作目录
-fn get_process_cwd(pid: u32) -> Option<String> {
-    let output = Command::new("lsof")
-        .args(["-p", &pid.to_string()])
-        .output()
-        .ok()?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    for line in stdout.lines() {
-        if line.contains("cwd") {
-            // lsof 输出格式: COMMAND PID USER FD TYPE DEVICE SIZE/OFF NODE NAME
-            // cwd 行的最后一列是目录路径
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 9 {
-                return Some(parts[8..].join(" "));
-            }
-        }
-    }
-    None
-}
-
-/// 获取正在运行的 AI CLI 进程（Claude Code、Gemini CLI、Codex CLI）
+/// 获取正在运行的 AI CLI 进程（按配置中的 CLI 注册表匹配）
 /// 作为 hooks 系统的兜底检测
-pub fn get_cli_processes() -> Vec<ProcessInfo> {
+pub fn get_cli_processes(registry: &CliRegistry) -> Vec<ProcessInfo> {
     let mut sys = System::new_all();
     sys.refresh_all();
+    cli_processes_in(&sys, registry)
+}
 
-    // 只检测 AI CLI 工具，不检测终端应用
-    let cli_patterns: &[&str] = &[
-        "claude", // Claude Code CLI
-        "gemini", // Gemini CLI
-        "codex",  // Codex CLI
-    ];
-
+/// 从已刷新的进程列表中找出 AI CLI 进程（资源采样线程复用自己的 System）
+pub fn cli_processes_in(sys: &System, registry: &CliRegistry) -> Vec<ProcessInfo> {
     let mut processes = Vec::new();
 
     for (pid, process) in sys.processes() {
-        let name = process.name().to_string_lossy().to_string();
-        let name_lower = name.to_lowercase();
+        // Linux 上 sysinfo 会把线程也列出来，跳过
+        if process.thread_kind().is_some() {
+            continue;
+        }
 
-        // 精确匹配 AI CLI 工具
-        let is_cli = cli_patterns
+        // 只检测注册表中的 AI CLI 工具，不检测终端应用
+        let name = process.name().to_string_lossy().to_string();
+        let argv: Vec<String> = process
+            .cmd()
             .iter()
-            .any(|pattern| name_lower == *pattern);
-
-        if is_cli {
-            let pid_u32 = pid.as_u32();
-
-            // 简化状态：只区分运行中和其他
-            let status = match process.status() {
-                sysinfo::ProcessStatus::Run => "running",
-                _ => "idle",
-            };
-
-            // 获取工作目录
-            let cwd = get_process_cwd(pid_u32);
-
-            processes.push(ProcessInfo {
-                name: name.clone(),
-                pid: pid_u32,
-                status: status.to_string(),
-                cwd,
-            });
-        }
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let cli_id = match registry.match_process(&name, &argv) {
+            Some(def) => def.id.clone(),
+            None => continue,
+        };
+
+        // 由 node/python 等运行时，进程名只是解释器，对外报告真正的 CLI 名
+        let (name, interpreter) = match script_target(&name, &argv) {
+            Some(_) => (cli_id.clone(), Some(name)),
+            None => (name, None),
+        };
+
+        let pid_u32 = pid.as_u32();
+
+        // 简化状态：只区分运行中和其他
+        let status = match process.status() {
+            sysinfo::ProcessStatus::Run => "running",
+            _ => "idle",
+        };
+
+        // 工作目录由 sysinfo 随进程列表一起读取，不再为每个进程单独查询（macOS 上要启动 lsof）
+        let cwd = process
+            .cwd()
+            .map(|path| path.to_string_lossy().into_owned());
+
+        processes.push(ProcessInfo {
+            name,
+            pid: pid_u32,
+            status: status.to_string(),
+            cwd,
+            cli_id,
+            interpreter,
+        });
     }
 
-    // 去重并只保留主要进程
-    processes.sort_by(|a, b| a.name.cmp(&b.name));
-    processes.dedup_by(|a, b| a.name.to_lowercase() == b.name.to_lowercase());
+    // 同一个 CLI 可能同时运行多个实例（不同项目），每个 PID 单独保留
+    processes.sort_by(|a, b| a.cli_id.cmp(&b.cli_id).then(a.pid.cmp(&b.pid)));
 
     processes
 }
+
+/// 校验 hook 上报的 PID：
+/// - 是存活的、属于这个 CLI 的进程：原样返回
+/// - 否则沿父进程链向上找属于这个 CLI 的进程（旧版客户端上报的是 hook 脚本自己的 PID）
+/// - 注册表中没有这个 CLI 时，只要进程存活就接受
+///
+/// 都不满足（进程已退出等）返回 None
+pub fn resolve_cli_pid(pid: u32, cli: &str, registry: &CliRegistry) -> Option<u32> {
+    let inspector = inspector();
+    let known = registry.get(cli).is_some();
+    let mut current = pid;
+    // 最多向上查找 10 层，防止循环
+    for _ in 0..10 {
+        let details = inspector.details(current)?;
+        if !known {
+            return Some(current);
+        }
+        let matched = registry
+            .match_process(&details.name, &details.argv)
+            .is_some_and(|def| def.id.eq_ignore_ascii_case(cli));
+        if matched {
+            return Some(current);
+        }
+        if details.ppid <= 1 {
+            return None;
+        }
+        current = details.ppid;
+    }
+    None
+}
+
+/// resolve_cli_pid 的结果缓存，按 (CLI, 上报的 PID) 保存
+/// 同一个会话的每个 hook 事件都会上报同一个 PID，只在第一次沿父进程链查找（macOS 上每层都要启动 ps），
+/// 上报的进程退出后（PID 可能被复用）缓存失效
+#[derive(Default)]
+pub struct PidCache {
+    resolved: Mutex<HashMap<(String, u32), Option<u32>>>,
+}
+
+impl PidCache {
+    pub fn resolve(&self, pid: u32, cli: &str, registry: &CliRegistry) -> Option<u32> {
+        let key = (cli.to_string(), pid);
+        if is_alive(pid) {
+            if let Some(resolved) = self.resolved.lock().unwrap().get(&key) {
+                return *resolved;
+            }
+        }
+
+        let resolved = resolve_cli_pid(pid, cli, registry);
+        let mut cache = self.resolved.lock().unwrap();
+        cache.retain(|(_, reported), _| is_alive(*reported));
+        cache.insert(key, resolved);
+        resolved
+    }
+}
+
+/// 进程是否存在（只发送空信号，不做任何事）
+fn is_alive(pid: u32) -> bool {
+    // 0 和负数表示进程组，不能用来检查单个进程
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        _ => return false,
+    };
+    // SAFETY: 信号 0 只检查进程是否存在和权限
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
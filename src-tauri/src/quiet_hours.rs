@@ -0,0 +1,270 @@
+//! 免打扰：按星期和时间段的静默时段、临时暂停（snooze）和会议模式
+//!
+//! 免打扰期间 CLI 等待和久坐提醒不发送，先放进队列，
+//! 结束时合并成一条汇总通知，避免回来后看不到错过了什么。
+
+use crate::channels::{Notification, NotifyEvent};
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// 星期（配置中写作 "mon" ... "sun"）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Day {
+    fn weekday(self) -> Weekday {
+        match self {
+            Day::Mon => Weekday::Mon,
+            Day::Tue => Weekday::Tue,
+            Day::Wed => Weekday::Wed,
+            Day::Thu => Weekday::Thu,
+            Day::Fri => Weekday::Fri,
+            Day::Sat => Weekday::Sat,
+            Day::Sun => Weekday::Sun,
+        }
+    }
+}
+
+fn all_days() -> Vec<Day> {
+    vec![
+        Day::Mon,
+        Day::Tue,
+        Day::Wed,
+        Day::Thu,
+        Day::Fri,
+        Day::Sat,
+        Day::Sun,
+    ]
+}
+
+/// 一个静默时段，如 {"days": ["mon","tue"], "start": "22:00", "end": "08:00"}
+/// end 早于 start 时跨越午夜，days 指开始的那一天
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuietWindow {
+    #[serde(default = "all_days")]
+    pub days: Vec<Day>,
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietWindow {
+    /// 解析 "HH:MM" 格式的开始和结束时间
+    pub fn times(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let parse = |s: &str| {
+            NaiveTime::parse_from_str(s, "%H:%M")
+                .map_err(|e| format!("invalid time {:?}: {}", s, e))
+        };
+        Ok((parse(&self.start)?, parse(&self.end)?))
+    }
+
+    fn on(&self, day: Weekday) -> bool {
+        self.days.iter().any(|d| d.weekday() == day)
+    }
+
+    /// 本地时间 at 是否落在这个时段内
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let Ok((start, end)) = self.times() else {
+            return false;
+        };
+        let (day, time) = (at.weekday(), at.time());
+        if start <= end {
+            self.on(day) && time >= start && time < end
+        } else {
+            (self.on(day) && time >= start) || (self.on(day.pred()) && time < end)
+        }
+    }
+}
+
+/// 当前处于免打扰的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuietReason {
+    Meeting,
+    Snooze,
+    Schedule,
+}
+
+/// Unix 时间戳（秒）转为本地时间
+pub fn local_datetime(ts: u64) -> NaiveDateTime {
+    Local
+        .timestamp_opt(ts as i64, 0)
+        .single()
+        .map(|t| t.naive_local())
+        .unwrap_or_default()
+}
+
+/// 本地时间 at 是否在任一静默时段内
+pub fn in_schedule(windows: &[QuietWindow], at: NaiveDateTime) -> bool {
+    windows.iter().any(|w| w.contains(at))
+}
+
+/// 免打扰期间需要暂缓的事件（卡住提醒和更新照常发送）
+pub fn is_suppressible(event: NotifyEvent) -> bool {
+    matches!(
+        event,
+        NotifyEvent::CliWaiting | NotifyEvent::SittingReminder
+    )
+}
+
+/// 免打扰期间暂缓的通知
+#[derive(Default)]
+pub struct QuietQueue {
+    quiet: bool,
+    held: Vec<Notification>,
+}
+
+impl QuietQueue {
+    pub fn hold(&mut self, notification: Notification) {
+        self.held.push(notification);
+    }
+
+    /// 更新当前是否处于免打扰，状态变化时返回 true
+    pub fn update(&mut self, quiet: bool) -> bool {
+        let changed = self.quiet != quiet;
+        self.quiet = quiet;
+        changed
+    }
+
+    /// 取出所有暂缓的通知
+    pub fn take(&mut self) -> Vec<Notification> {
+        std::mem::take(&mut self.held)
+    }
+}
+
+/// 把暂缓的通知合并为一条，每条通知取正文第一行并去重
+pub fn summary_body(held: &[Notification]) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for n in held {
+        let line = n.body.lines().next().unwrap_or(&n.title);
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn window(days: &[Day], start: &str, end: &str) -> QuietWindow {
+        QuietWindow {
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    /// 2024-01-01 是星期一
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    fn notification(event: NotifyEvent, title: &str, body: &str) -> Notification {
+        Notification::new(event, title, body, false)
+    }
+
+    #[test]
+    fn window_includes_start_and_excludes_end() {
+        let w = window(&[Day::Mon], "09:00", "17:00");
+        assert!(w.contains(at(1, "09:00")));
+        assert!(w.contains(at(1, "16:59")));
+        assert!(!w.contains(at(1, "08:59")));
+        assert!(!w.contains(at(1, "17:00")));
+        // 其他日子不生效
+        assert!(!w.contains(at(2, "10:00")));
+    }
+
+    #[test]
+    fn window_wrapping_midnight_belongs_to_its_start_day() {
+        let w = window(&[Day::Fri], "22:00", "08:00");
+        // 2024-01-05 星期五，01-06 星期六
+        assert!(w.contains(at(5, "22:00")));
+        assert!(w.contains(at(5, "23:59")));
+        assert!(w.contains(at(6, "00:00")));
+        assert!(w.contains(at(6, "07:59")));
+        assert!(!w.contains(at(6, "08:00")));
+        assert!(!w.contains(at(5, "21:59")));
+        // 星期五凌晨属于星期四开始的时段，星期四没有配置
+        assert!(!w.contains(at(5, "07:00")));
+        // 星期六晚上不是开始日
+        assert!(!w.contains(at(6, "23:00")));
+    }
+
+    #[test]
+    fn sunday_window_continues_into_monday() {
+        let w = window(&[Day::Sun], "23:00", "02:00");
+        // 2024-01-07 星期日，01-08 星期一
+        assert!(w.contains(at(7, "23:30")));
+        assert!(w.contains(at(8, "01:59")));
+        assert!(!w.contains(at(8, "02:00")));
+        assert!(!w.contains(at(9, "01:00")));
+    }
+
+    #[test]
+    fn invalid_times_never_match() {
+        let w = window(&[Day::Mon], "9am", "17:00");
+        assert!(w.times().is_err());
+        assert!(!w.contains(at(1, "10:00")));
+        assert!(!in_schedule(std::slice::from_ref(&w), at(1, "10:00")));
+        assert!(in_schedule(
+            &[w, window(&[Day::Mon], "10:00", "11:00")],
+            at(1, "10:00")
+        ));
+    }
+
+    #[test]
+    fn queue_reports_transitions_and_hands_back_held() {
+        let mut queue = QuietQueue::default();
+        assert!(!queue.update(false));
+        assert!(queue.update(true));
+        assert!(!queue.update(true));
+
+        queue.hold(notification(NotifyEvent::CliWaiting, "t", "a"));
+        queue.hold(notification(NotifyEvent::SittingReminder, "t", "b"));
+        assert!(queue.update(false));
+        let held = queue.take();
+        assert_eq!(held.len(), 2);
+        assert_eq!(held[0].body, "a");
+        assert!(queue.take().is_empty());
+    }
+
+    #[test]
+    fn summary_uses_first_lines_without_duplicates() {
+        let held = [
+            notification(
+                NotifyEvent::CliWaiting,
+                "Focus Guard",
+                "Claude 等待输入\n详情",
+            ),
+            notification(NotifyEvent::CliWaiting, "Focus Guard", "Claude 等待输入"),
+            notification(NotifyEvent::SittingReminder, "该休息了", ""),
+            notification(NotifyEvent::CliWaiting, "Focus Guard", "Codex 等待输入"),
+        ];
+        assert_eq!(
+            summary_body(&held),
+            "Claude 等待输入\n该休息了\nCodex 等待输入"
+        );
+        assert_eq!(summary_body(&[]), "");
+    }
+
+    #[test]
+    fn only_waits_and_sitting_reminders_are_held() {
+        assert!(is_suppressible(NotifyEvent::CliWaiting));
+        assert!(is_suppressible(NotifyEvent::SittingReminder));
+        assert!(!is_suppressible(NotifyEvent::CliStalled));
+        assert!(!is_suppressible(NotifyEvent::Update));
+        assert!(!is_suppressible(NotifyEvent::QuietSummary));
+    }
+}
@@ -0,0 +1,576 @@
+//! 日报 / 周报：汇总久坐时间、休息次数、提醒次数和各项目 Agent 工作/等待时长
+//!
+//! 同时输出自包含的 HTML、Markdown 和 JSON，写入应用数据目录下的 reports/，
+//! 可从托盘菜单手动生成，也可以在每天结束时自动生成。
+
+use crate::analytics::{self, LatencyStats};
+use crate::config::app_data_dir;
+use crate::history::{HistoryRecord, HistoryStore, ResetReason};
+use crate::i18n::{format_duration, Language};
+use chrono::{Datelike, Days, Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 报表周期
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportPeriod {
+    Daily,
+    Weekly, // 周一到周日
+}
+
+impl ReportPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportPeriod::Daily => "daily",
+            ReportPeriod::Weekly => "weekly",
+        }
+    }
+
+    /// 包含 date 的周期的第一天和最后一天
+    pub fn date_range(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            ReportPeriod::Daily => (date, date),
+            ReportPeriod::Weekly => {
+                let monday = date - Days::new(u64::from(date.weekday().num_days_from_monday()));
+                (monday, monday + Days::new(6))
+            }
+        }
+    }
+}
+
+/// 单个项目的统计
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectReport {
+    pub project: String,
+    pub cwd: Option<String>,
+    pub working_secs: u64,
+    pub waiting_secs: u64,
+    pub response: LatencyStats,
+}
+
+/// 一份报表
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub period: ReportPeriod,
+    pub start_date: String,
+    pub end_date: String,
+    pub generated_at: String,
+    pub sitting_minutes: u32,
+    pub breaks: usize,
+    pub manual_resets: usize,
+    pub reminders: usize,
+    pub working_secs: u64,
+    pub waiting_secs: u64,
+    pub response: LatencyStats,
+    pub projects: Vec<ProjectReport>,
+}
+
+/// 报表中的文字
+struct Labels {
+    daily_title: &'static str,
+    weekly_title: &'static str,
+    generated_at: &'static str,
+    metric: &'static str,
+    value: &'static str,
+    sitting: &'static str,
+    breaks: &'static str,
+    manual_resets: &'static str,
+    reminders: &'static str,
+    agent_working: &'static str,
+    agent_waiting: &'static str,
+    waits: &'static str,
+    median: &'static str,
+    p90: &'static str,
+    projects: &'static str,
+    project: &'static str,
+    no_projects: &'static str,
+}
+
+const LABELS_EN: Labels = Labels {
+    daily_title: "Focus Guard Daily Report",
+    weekly_title: "Focus Guard Weekly Report",
+    generated_at: "Generated at",
+    metric: "Metric",
+    value: "Value",
+    sitting: "Sitting time",
+    breaks: "Breaks taken",
+    manual_resets: "Manual timer resets",
+    reminders: "Sitting reminders",
+    agent_working: "Agent working",
+    agent_waiting: "Agent waiting for you",
+    waits: "Waits",
+    median: "Median response",
+    p90: "P90 response",
+    projects: "Projects",
+    project: "Project",
+    no_projects: "No agent activity recorded.",
+};
+
+const LABELS_ZH: Labels = Labels {
+    daily_title: "Focus Guard 日报",
+    weekly_title: "Focus Guard 周报",
+    generated_at: "生成时间",
+    metric: "指标",
+    value: "数值",
+    sitting: "久坐时间",
+    breaks: "休息次数",
+    manual_resets: "手动重置计时",
+    reminders: "久坐提醒次数",
+    agent_working: "Agent 工作",
+    agent_waiting: "Agent 等待你",
+    waits: "等待次数",
+    median: "响应中位数",
+    p90: "响应 P90",
+    projects: "项目",
+    project: "项目",
+    no_projects: "没有 Agent 活动记录。",
+};
+
+fn labels(lang: Language) -> &'static Labels {
+    match lang {
+        Language::English => &LABELS_EN,
+        Language::Chinese => &LABELS_ZH,
+    }
+}
+
+/// 报表输出目录
+pub fn reports_dir() -> PathBuf {
+    app_data_dir().join("reports")
+}
+
+/// 根据历史记录生成包含 date 的周期报表
+/// now 为当前时间戳，current_sitting_minutes 为尚未重置的久坐分钟数（周期包含当前时间时计入）
+pub fn build_report(
+    records: &[HistoryRecord],
+    period: ReportPeriod,
+    date: NaiveDate,
+    now: u64,
+    current_sitting_minutes: u32,
+) -> Report {
+    let (first, last) = period.date_range(date);
+    let start = analytics::local_day_start(first);
+    let end = analytics::local_day_start(last + Days::new(1));
+    let in_range = |ts: u64| ts >= start && ts < end;
+
+    let mut sitting_minutes = 0;
+    let mut breaks = 0;
+    let mut manual_resets = 0;
+    let mut reminders = 0;
+    for record in records.iter().filter(|r| in_range(r.ts())) {
+        match record {
+            HistoryRecord::SittingReset {
+                minutes, reason, ..
+            } => {
+                sitting_minutes += minutes;
+                match reason {
+                    ResetReason::Break => breaks += 1,
+                    ResetReason::Manual => manual_resets += 1,
+                }
+            }
+            HistoryRecord::SittingReminder { .. } => reminders += 1,
+            HistoryRecord::Transition { .. } => {}
+        }
+    }
+    if in_range(now) {
+        sitting_minutes += current_sitting_minutes;
+    }
+
+    // 每个项目的响应延迟
+    let waits: Vec<_> = analytics::collect_waits(records)
+        .into_iter()
+        .filter(|w| in_range(w.started_at))
+        .collect();
+    let mut waits_by_cwd: HashMap<Option<String>, Vec<u64>> = HashMap::new();
+    for wait in &waits {
+        waits_by_cwd
+            .entry(wait.cwd.clone())
+            .or_default()
+            .push(wait.secs);
+    }
+
+    let projects: Vec<ProjectReport> = analytics::project_times(records, start, end.min(now))
+        .into_iter()
+        .map(|p| ProjectReport {
+            response: LatencyStats::from_secs(waits_by_cwd.remove(&p.cwd).unwrap_or_default()),
+            project: p.project,
+            cwd: p.cwd,
+            working_secs: p.working_secs,
+            waiting_secs: p.waiting_secs,
+        })
+        .collect();
+
+    Report {
+        period,
+        start_date: first.format("%Y-%m-%d").to_string(),
+        end_date: last.format("%Y-%m-%d").to_string(),
+        generated_at: Local
+            .timestamp_opt(now as i64, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default(),
+        sitting_minutes,
+        breaks,
+        manual_resets,
+        reminders,
+        working_secs: projects.iter().map(|p| p.working_secs).sum(),
+        waiting_secs: projects.iter().map(|p| p.waiting_secs).sum(),
+        response: LatencyStats::from_secs(waits.iter().map(|w| w.secs).collect()),
+        projects,
+    }
+}
+
+/// 读取历史并生成报表
+pub fn generate(
+    history: &HistoryStore,
+    period: ReportPeriod,
+    date: NaiveDate,
+    now: u64,
+    current_sitting_minutes: u32,
+) -> Report {
+    let (first, _) = period.date_range(date);
+    let records = history.read_since(analytics::local_day_start(first));
+    build_report(&records, period, date, now, current_sitting_minutes)
+}
+
+fn title(report: &Report, l: &Labels) -> String {
+    let name = match report.period {
+        ReportPeriod::Daily => l.daily_title,
+        ReportPeriod::Weekly => l.weekly_title,
+    };
+    if report.start_date == report.end_date {
+        format!("{} · {}", name, report.start_date)
+    } else {
+        format!("{} · {} ~ {}", name, report.start_date, report.end_date)
+    }
+}
+
+/// 汇总表的行
+fn summary_rows(report: &Report, l: &Labels) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            l.sitting,
+            format_duration(u64::from(report.sitting_minutes) * 60),
+        ),
+        (l.breaks, report.breaks.to_string()),
+        (l.manual_resets, report.manual_resets.to_string()),
+        (l.reminders, report.reminders.to_string()),
+        (l.agent_working, format_duration(report.working_secs)),
+        (l.agent_waiting, format_duration(report.waiting_secs)),
+        (l.waits, report.response.count.to_string()),
+        (l.median, format_duration(report.response.median_secs)),
+        (l.p90, format_duration(report.response.p90_secs)),
+    ]
+}
+
+/// 项目表的行
+fn project_rows(report: &Report) -> Vec<[String; 6]> {
+    report
+        .projects
+        .iter()
+        .map(|p| {
+            [
+                p.project.clone(),
+                format_duration(p.working_secs),
+                format_duration(p.waiting_secs),
+                p.response.count.to_string(),
+                format_duration(p.response.median_secs),
+                format_duration(p.response.p90_secs),
+            ]
+        })
+        .collect()
+}
+
+fn project_headers(l: &Labels) -> [&'static str; 6] {
+    [
+        l.project,
+        l.agent_working,
+        l.agent_waiting,
+        l.waits,
+        l.median,
+        l.p90,
+    ]
+}
+
+pub fn render_markdown(report: &Report, lang: Language) -> String {
+    let l = labels(lang);
+    let mut out = format!("# {}\n\n", title(report, l));
+    out.push_str(&format!("{}: {}\n\n", l.generated_at, report.generated_at));
+
+    out.push_str(&format!("| {} | {} |\n|---|---|\n", l.metric, l.value));
+    for (name, value) in summary_rows(report, l) {
+        out.push_str(&format!("| {} | {} |\n", name, value));
+    }
+
+    out.push_str(&format!("\n## {}\n\n", l.projects));
+    if report.projects.is_empty() {
+        out.push_str(l.no_projects);
+        out.push('\n');
+        return out;
+    }
+    out.push_str(&format!("| {} |\n", project_headers(l).join(" | ")));
+    out.push_str("|---|---|---|---|---|---|\n");
+    for row in project_rows(report) {
+        let cells: Vec<String> = row.iter().map(|c| c.replace('|', "\\|")).collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render_html(report: &Report, lang: Language) -> String {
+    let l = labels(lang);
+    let title = escape_html(&title(report, l));
+    let mut body = format!(
+        "<h1>{}</h1>\n<p class=\"meta\">{}: {}</p>\n",
+        title,
+        l.generated_at,
+        escape_html(&report.generated_at)
+    );
+
+    body.push_str(&format!(
+        "<table>\n<tr><th>{}</th><th>{}</th></tr>\n",
+        l.metric, l.value
+    ));
+    for (name, value) in summary_rows(report, l) {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            name,
+            escape_html(&value)
+        ));
+    }
+    body.push_str("</table>\n");
+
+    body.push_str(&format!("<h2>{}</h2>\n", l.projects));
+    if report.projects.is_empty() {
+        body.push_str(&format!("<p>{}</p>\n", l.no_projects));
+    } else {
+        body.push_str("<table>\n<tr>");
+        for header in project_headers(l) {
+            body.push_str(&format!("<th>{}</th>", header));
+        }
+        body.push_str("</tr>\n");
+        for (row, project) in project_rows(report).iter().zip(&report.projects) {
+            let cwd = escape_html(project.cwd.as_deref().unwrap_or(""));
+            body.push_str(&format!(
+                "<tr><td title=\"{}\">{}</td>",
+                cwd,
+                escape_html(&row[0])
+            ));
+            for cell in &row[1..] {
+                body.push_str(&format!("<td>{}</td>", escape_html(cell)));
+            }
+            body.push_str("</tr>\n");
+        }
+        body.push_str("</table>\n");
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", "PingFang SC", sans-serif; max-width: 760px; margin: 2em auto; color: #222; }}
+h1 {{ font-size: 1.4em; }}
+.meta {{ color: #888; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }}
+th, td {{ border-bottom: 1px solid #e4e4e4; padding: 6px 10px; text-align: left; }}
+th {{ background: #f6f6f6; }}
+</style>
+</head>
+<body>
+{body}</body>
+</html>
+"#
+    )
+}
+
+/// 写出 HTML、Markdown、JSON 三种格式，返回写入的文件路径（HTML 在前）
+pub fn write_report(report: &Report, lang: Language, dir: &Path) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let stem = format!("{}-{}", report.period.as_str(), report.start_date);
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+
+    let outputs = [
+        ("html", render_html(report, lang)),
+        ("md", render_markdown(report, lang)),
+        ("json", json),
+    ];
+    let mut paths = Vec::new();
+    for (ext, content) in outputs {
+        let path = dir.join(format!("{}.{}", stem, ext));
+        fs::write(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// 用系统默认程序打开报表
+pub fn open_report(path: &Path) {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    if let Err(e) = Command::new(opener).arg(path).spawn() {
+        eprintln!("Failed to open {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_manager::CliState;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    /// 2024-01-day 本地 0 点之后 secs 秒
+    fn at(day: u32, secs: u64) -> u64 {
+        analytics::local_day_start(date(day)) + secs
+    }
+
+    fn reset(ts: u64, minutes: u32, reason: ResetReason) -> HistoryRecord {
+        HistoryRecord::SittingReset {
+            ts,
+            minutes,
+            reason,
+        }
+    }
+
+    fn transition(ts: u64, cwd: &str, from: CliState, to: CliState) -> HistoryRecord {
+        HistoryRecord::Transition {
+            ts,
+            cli: "claude".to_string(),
+            session_id: Some("s".to_string()),
+            cwd: Some(cwd.to_string()),
+            from,
+            to,
+        }
+    }
+
+    #[test]
+    fn weekly_range_runs_monday_to_sunday() {
+        // 2024-01-01 是星期一
+        assert_eq!(ReportPeriod::Daily.date_range(date(3)), (date(3), date(3)));
+        assert_eq!(ReportPeriod::Weekly.date_range(date(3)), (date(1), date(7)));
+        assert_eq!(ReportPeriod::Weekly.date_range(date(1)), (date(1), date(7)));
+        assert_eq!(ReportPeriod::Weekly.date_range(date(7)), (date(1), date(7)));
+        assert_eq!(
+            ReportPeriod::Weekly.date_range(date(8)),
+            (date(8), date(14))
+        );
+    }
+
+    #[test]
+    fn daily_report_counts_only_records_inside_the_day() {
+        let records = [
+            reset(at(2, 0) - 1, 100, ResetReason::Break),
+            reset(at(2, 0), 40, ResetReason::Break),
+            HistoryRecord::SittingReminder {
+                ts: at(2, 3600),
+                minutes: 45,
+            },
+            reset(at(3, 0) - 1, 20, ResetReason::Manual),
+            reset(at(3, 0), 100, ResetReason::Break),
+        ];
+        let report = build_report(&records, ReportPeriod::Daily, date(2), at(5, 0), 15);
+        assert_eq!(report.start_date, "2024-01-02");
+        assert_eq!(report.end_date, "2024-01-02");
+        assert_eq!(report.sitting_minutes, 60);
+        assert_eq!(report.breaks, 1);
+        assert_eq!(report.manual_resets, 1);
+        assert_eq!(report.reminders, 1);
+
+        // 生成时间在当天时计入尚未重置的久坐时间
+        let today = build_report(&records, ReportPeriod::Daily, date(2), at(2, 7200), 15);
+        assert_eq!(today.sitting_minutes, 75);
+    }
+
+    #[test]
+    fn weekly_report_covers_the_whole_week() {
+        let records = [
+            reset(at(1, 0), 10, ResetReason::Break),
+            reset(at(7, 86399), 20, ResetReason::Break),
+            reset(at(8, 0), 40, ResetReason::Break),
+        ];
+        let report = build_report(&records, ReportPeriod::Weekly, date(4), at(9, 0), 0);
+        assert_eq!(report.start_date, "2024-01-01");
+        assert_eq!(report.end_date, "2024-01-07");
+        assert_eq!(report.sitting_minutes, 30);
+        assert_eq!(report.breaks, 2);
+    }
+
+    #[test]
+    fn agent_time_is_cut_at_the_period_end_or_now() {
+        let records = [
+            transition(
+                at(2, 3600),
+                "/work/app",
+                CliState::Offline,
+                CliState::Working,
+            ),
+            transition(
+                at(2, 3600 + 600),
+                "/work/app",
+                CliState::Working,
+                CliState::WaitingInput,
+            ),
+            transition(
+                at(2, 3600 + 900),
+                "/work/app",
+                CliState::WaitingInput,
+                CliState::Working,
+            ),
+        ];
+        // 会话在当天结束时仍在工作，计到 24 点
+        let report = build_report(&records, ReportPeriod::Daily, date(2), at(3, 60), 0);
+        assert_eq!(report.projects.len(), 1);
+        assert_eq!(report.projects[0].project, "app");
+        assert_eq!(report.projects[0].waiting_secs, 300);
+        assert_eq!(report.working_secs, 600 + (86400 - 3600 - 900));
+        assert_eq!(report.response.count, 1);
+        assert_eq!(report.projects[0].response.median_secs, 300);
+
+        // 当天还没结束时只计到现在
+        let report = build_report(
+            &records,
+            ReportPeriod::Daily,
+            date(2),
+            at(2, 3600 + 1000),
+            0,
+        );
+        assert_eq!(report.working_secs, 700);
+    }
+
+    #[test]
+    fn renderers_escape_project_names() {
+        let records = [transition(
+            at(2, 0),
+            "/work/<a|b>",
+            CliState::Offline,
+            CliState::Working,
+        )];
+        let report = build_report(&records, ReportPeriod::Daily, date(2), at(2, 60), 0);
+        let markdown = render_markdown(&report, Language::English);
+        assert!(markdown.starts_with("# Focus Guard Daily Report · 2024-01-02\n"));
+        assert!(markdown.contains("| <a\\|b> |"));
+        let html = render_html(&report, Language::Chinese);
+        assert!(html.contains("<title>Focus Guard 日报 · 2024-01-02</title>"));
+        assert!(html.contains("&lt;a|b&gt;"));
+        assert!(!html.contains("<a|b>"));
+    }
+}
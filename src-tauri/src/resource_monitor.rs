@@ -0,0 +1,211 @@
+//! 会话资源占用：CPU、内存和子进程数
+//!
+//! 后台线程定期刷新同一个 sysinfo::System（CPU 占用需要两次刷新之间的差值），
+//! 为每个会话找到对应的 CLI 进程，统计它和所有子孙进程的占用，
+//! 这样 Agent 启动的测试或构建也算在会话头上。
+
+use crate::cli_registry::CliRegistry;
+use crate::process_monitor::{self, ProcessInfo};
+use crate::state_manager::CliStatus;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+/// 一个会话（CLI 进程及其子孙进程）的资源占用
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// CPU 占用百分比，多核时可能超过 100
+    pub cpu_percent: f32,
+    /// 常驻内存（字节）
+    pub rss_bytes: u64,
+    /// 子孙进程数量（不含 CLI 进程本身）
+    pub children: u32,
+}
+
+/// CPU 占用低于这个百分比视为没有活动（等待 API 响应的 CLI 通常在 1% 以下）
+const ACTIVE_CPU_PERCENT: f32 = 2.0;
+
+impl ResourceUsage {
+    /// 进程树是否在占用 CPU（用于卡住检测）
+    pub fn is_active(&self) -> bool {
+        self.cpu_percent >= ACTIVE_CPU_PERCENT
+    }
+}
+
+/// 资源采样器，在同一个线程中反复刷新
+#[derive(Default)]
+pub struct ResourceSampler {
+    sys: System,
+    /// 父进程 -> 子进程，每次刷新后重建
+    children: HashMap<Pid, Vec<Pid>>,
+}
+
+impl ResourceSampler {
+    /// 刷新所有进程的 CPU、内存、命令行和工作目录
+    /// 命令行和工作目录只在进程第一次出现时读取
+    pub fn refresh(&mut self) {
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::new()
+                .with_cpu()
+                .with_memory()
+                .with_cmd(UpdateKind::OnlyIfNotSet)
+                .with_cwd(UpdateKind::OnlyIfNotSet),
+        );
+
+        self.children.clear();
+        for (pid, process) in self.sys.processes() {
+            // 线程与所属进程共享内存，不单独计入
+            if process.thread_kind().is_some() {
+                continue;
+            }
+            if let Some(parent) = process.parent() {
+                self.children.entry(parent).or_default().push(*pid);
+            }
+        }
+    }
+
+    /// 当前的 AI CLI 进程
+    pub fn cli_processes(&self, registry: &CliRegistry) -> Vec<ProcessInfo> {
+        process_monitor::cli_processes_in(&self.sys, registry)
+    }
+
+    /// pid 及其子孙进程的资源占用，进程不存在时返回 None
+    pub fn usage(&self, pid: u32) -> Option<ResourceUsage> {
+        let root = Pid::from_u32(pid);
+        let process = self.sys.process(root)?;
+        let mut usage = ResourceUsage {
+            cpu_percent: process.cpu_usage(),
+            rss_bytes: process.memory(),
+            children: 0,
+        };
+
+        let mut stack = self.children.get(&root).cloned().unwrap_or_default();
+        while let Some(child) = stack.pop() {
+            if let Some(process) = self.sys.process(child) {
+                usage.cpu_percent += process.cpu_usage();
+                usage.rss_bytes += process.memory();
+                usage.children += 1;
+            }
+            if let Some(grandchildren) = self.children.get(&child) {
+                stack.extend(grandchildren);
+            }
+        }
+        Some(usage)
+    }
+}
+
+/// 会话对应的 CLI 进程
+/// hooks 上报的 PID 可能是已退出的 hook 脚本，找不到时按同一 CLI、同一 cwd 匹配
+pub fn session_pid(status: &CliStatus, processes: &[ProcessInfo]) -> Option<u32> {
+    let same_cli = |p: &&ProcessInfo| p.cli_id.eq_ignore_ascii_case(&status.cli_name);
+    if let Some(pid) = status.pid {
+        if processes.iter().filter(same_cli).any(|p| p.pid == pid) {
+            return Some(pid);
+        }
+    }
+    let cwd = status.cwd.as_ref()?;
+    processes
+        .iter()
+        .filter(same_cli)
+        .find(|p| p.cwd.as_ref() == Some(cwd))
+        .map(|p| p.pid)
+}
+
+/// 菜单中显示的简短占用，如 "12% · 340 MB · +3"（+N 为子进程数）
+pub fn format_usage(usage: &ResourceUsage) -> String {
+    let mb = usage.rss_bytes as f64 / (1024.0 * 1024.0);
+    let memory = if mb >= 1024.0 {
+        format!("{:.1} GB", mb / 1024.0)
+    } else {
+        format!("{:.0} MB", mb)
+    };
+    if usage.children > 0 {
+        format!(
+            "{:.0}% · {} · +{}",
+            usage.cpu_percent, memory, usage.children
+        )
+    } else {
+        format!("{:.0}% · {}", usage.cpu_percent, memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, cli_id: &str, cwd: &str) -> ProcessInfo {
+        ProcessInfo {
+            name: cli_id.to_string(),
+            pid,
+            status: "Run".to_string(),
+            cwd: Some(cwd.to_string()),
+            cli_id: cli_id.to_string(),
+            interpreter: None,
+        }
+    }
+
+    fn status(cli: &str, pid: Option<u32>, cwd: Option<&str>) -> CliStatus {
+        let mut status = CliStatus::new(cli.to_string());
+        status.pid = pid;
+        status.cwd = cwd.map(String::from);
+        status
+    }
+
+    #[test]
+    fn session_pid_prefers_reported_pid_then_cwd() {
+        let processes = [
+            process(10, "claude", "/work/app"),
+            process(20, "codex", "/work/lib"),
+            process(30, "claude", "/work/lib"),
+        ];
+        assert_eq!(
+            session_pid(&status("Claude", Some(10), None), &processes),
+            Some(10)
+        );
+        // 上报的是已退出的 hook 脚本，按 cwd 找
+        let hook = status("claude", Some(99), Some("/work/lib"));
+        assert_eq!(session_pid(&hook, &processes), Some(30));
+        // 其他 CLI 的进程不算
+        let other = status("claude", Some(20), Some("/work/none"));
+        assert_eq!(session_pid(&other, &processes), None);
+        assert_eq!(session_pid(&status("claude", None, None), &processes), None);
+    }
+
+    #[test]
+    fn usage_is_formatted_compactly() {
+        let usage = ResourceUsage {
+            cpu_percent: 12.4,
+            rss_bytes: 340 * 1024 * 1024,
+            children: 0,
+        };
+        assert_eq!(format_usage(&usage), "12% · 340 MB");
+        let usage = ResourceUsage {
+            cpu_percent: 150.0,
+            rss_bytes: 3 * 1024 * 1024 * 1024 / 2,
+            children: 3,
+        };
+        assert_eq!(format_usage(&usage), "150% · 1.5 GB · +3");
+        assert!(usage.is_active());
+        assert!(!ResourceUsage::default().is_active());
+    }
+
+    #[test]
+    fn usage_includes_child_processes() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+        let mut sampler = ResourceSampler::default();
+        sampler.refresh();
+        let usage = sampler.usage(std::process::id());
+        let _ = child.kill();
+        let _ = child.wait();
+
+        let usage = usage.unwrap();
+        assert!(usage.children >= 1);
+        assert!(usage.rss_bytes > 0);
+        assert_eq!(sampler.usage(u32::MAX), None);
+    }
+}
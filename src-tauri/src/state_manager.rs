@@ -1,8 +1,11 @@
+use crate::clock::{SharedClock, SystemClock};
 use crate::ipc_server::{CliEvent, CliMessage};
+use crate::machine::{Effect, Input, Machine, TimingTable};
+use crate::resource_monitor::ResourceUsage;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -12,6 +15,7 @@ pub enum CliState {
     Working,      // AI 正在处理
     WaitingInput, // 等待用户输入
     Idle,         // 空闲
+    Stalled,      // 长时间没有事件、进程也没有 CPU 占用，可能卡住了
     Offline,      // 未运行
 }
 
@@ -27,6 +31,8 @@ pub struct CliStatus {
     pub cwd: Option<String>,
     pub display_name: String, // 如 "Claude - my-project"
     pub stop_received_at: Option<Instant>, // Stop 事件接收时间，用于延迟判断
+    pub resources: Option<ResourceUsage>,   // 最近一次采样的资源占用（找不到进程时为 None）
+    pub last_cpu_activity: Option<Instant>, // 最近一次采样到 CPU 占用的时间，用于卡住检测
 }
 
 impl CliStatus {
@@ -43,6 +49,8 @@ impl CliStatus {
             cwd: None,
             display_name,
             stop_received_at: None,
+            resources: None,
+            last_cpu_activity: None,
         }
     }
 
@@ -58,9 +66,18 @@ impl CliStatus {
             cwd,
             display_name,
             stop_received_at: None,
+            resources: None,
+            last_cpu_activity: None,
         }
     }
 
+    /// 是否为兜底进程检测创建的会话（没有 hooks，session_id 为 "pid:<PID>"）
+    pub fn is_fallback(&self) -> bool {
+        self.session_id
+            .as_deref()
+            .is_some_and(|sid| sid.starts_with(FALLBACK_SESSION_PREFIX))
+    }
+
     fn format_display_name(cli_name: &str, cwd: Option<&str>) -> String {
         let cli_display = capitalize_first(cli_name);
         match cwd {
@@ -78,6 +95,40 @@ impl CliStatus {
     pub fn update_display_name(&mut self) {
         self.display_name = Self::format_display_name(&self.cli_name, self.cwd.as_deref());
     }
+
+    /// 生成可序列化的会话快照（用于 IPC 查询）
+    pub fn snapshot(&self, key: &str, now: Instant) -> SessionSnapshot {
+        SessionSnapshot {
+            key: key.to_string(),
+            cli: self.cli_name.clone(),
+            display_name: self.display_name.clone(),
+            state: self.state,
+            session_id: self.session_id.clone(),
+            cwd: self.cwd.clone(),
+            pid: self.pid,
+            last_event: self.last_event.clone(),
+            idle_secs: now.saturating_duration_since(self.last_update).as_secs(),
+            resources: self.resources,
+        }
+    }
+}
+
+/// 兜底检测的会话没有真正的 session_id，用 "pid:<PID>" 代替
+pub const FALLBACK_SESSION_PREFIX: &str = "pid:";
+
+/// 会话快照，CliStatus 中的 Instant 无法序列化，这里换成距上次事件的秒数
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSnapshot {
+    pub key: String,
+    pub cli: String,
+    pub display_name: String,
+    pub state: CliState,
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub pid: Option<u32>,
+    pub last_event: Option<CliEvent>,
+    pub idle_secs: u64,
+    pub resources: Option<ResourceUsage>,
 }
 
 fn capitalize_first(s: &str) -> String {
@@ -89,7 +140,7 @@ fn capitalize_first(s: &str) -> String {
 }
 
 /// 生成状态 key，支持多实例
-fn make_state_key(cli: &str, session_id: Option<&str>) -> String {
+pub fn make_state_key(cli: &str, session_id: Option<&str>) -> String {
     match session_id {
         Some(sid) => format!("{}:{}", cli, sid),
         None => cli.to_string(),
@@ -97,7 +148,7 @@ fn make_state_key(cli: &str, session_id: Option<&str>) -> String {
 }
 
 /// 状态变化事件，包含详细信息
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct StateChangeEvent {
     pub state: CliState,
     pub pid: Option<u32>,
@@ -106,209 +157,103 @@ pub struct StateChangeEvent {
     pub state_changed: bool, // 聚合状态是否变化（用于判断是否需要通知）
 }
 
-/// 状态管理器
+/// 状态管理器：在后台线程中驱动状态机
 pub struct StateManager {
     /// 各 CLI 的状态
     cli_states: Arc<Mutex<HashMap<String, CliStatus>>>,
-    /// 等待输入超时时间（秒）
-    waiting_timeout: Duration,
+    /// 时间来源
+    clock: SharedClock,
+    /// Stop 延迟和 Idle 升级时间（可按 CLI 覆盖）
+    timings: TimingTable,
 }
 
 impl StateManager {
     pub fn new() -> Self {
+        Self::with_states(Arc::new(Mutex::new(HashMap::new())), SystemClock::shared())
+    }
+
+    /// 使用外部共享的状态表和时钟创建
+    pub fn with_states(
+        cli_states: Arc<Mutex<HashMap<String, CliStatus>>>,
+        clock: SharedClock,
+    ) -> Self {
         Self {
-            cli_states: Arc::new(Mutex::new(HashMap::new())),
-            waiting_timeout: Duration::from_secs(10), // Stop 后 10 秒无活动视为等待输入
+            cli_states,
+            clock,
+            timings: TimingTable::default(),
         }
     }
 
-    /// 获取状态的共享引用
-    pub fn get_states(&self) -> Arc<Mutex<HashMap<String, CliStatus>>> {
-        self.cli_states.clone()
+    /// 使用配置中的时间参数
+    pub fn with_timings(mut self, timings: TimingTable) -> Self {
+        self.timings = timings;
+        self
     }
 
-    /// 启动状态管理循环
+    /// 启动状态管理循环，状态机产生的副作用交给 on_effect 执行
     pub fn start(
         self,
         receiver: Receiver<CliMessage>,
-        on_state_change: impl Fn(StateChangeEvent) + Send + 'static,
+        mut on_effect: impl FnMut(Effect) + Send + 'static,
     ) {
-        let states = self.cli_states.clone();
-        let timeout = self.waiting_timeout;
-
-        std::thread::spawn(move || {
-            let mut last_aggregate_state = CliState::Offline;
-
-            loop {
-                // 非阻塞接收消息，超时 1 秒
-                match receiver.recv_timeout(Duration::from_secs(1)) {
-                    Ok(msg) => {
-                        let mut states_guard = states.lock().unwrap();
-                        let key = make_state_key(&msg.cli, msg.session_id.as_deref());
-                        let status = states_guard.entry(key).or_insert_with(|| {
-                            CliStatus::with_details(
-                                msg.cli.clone(),
-                                msg.session_id.clone(),
-                                msg.cwd.clone(),
-                            )
-                        });
-
-                        status.last_event = Some(msg.event.clone());
-                        status.last_update = Instant::now();
-                        status.pid = msg.pid;
-
-                        // 更新 session_id 和 cwd（如果有新值）
-                        if msg.session_id.is_some() {
-                            status.session_id = msg.session_id.clone();
-                        }
-                        if msg.cwd.is_some() {
-                            status.cwd = msg.cwd.clone();
-                            status.update_display_name();
-                        }
-
-                        // 处理 Stop 事件的延迟逻辑
-                        let new_state = match msg.event {
-                            CliEvent::SessionStart => {
-                                status.stop_received_at = None;
-                                CliState::Working
-                            }
-                            CliEvent::SessionEnd => {
-                                status.stop_received_at = None;
-                                CliState::Offline
-                            }
-                            CliEvent::Working => {
-                                // 收到 Working 事件，清除 Stop 记录
-                                status.stop_received_at = None;
-                                CliState::Working
-                            }
-                            CliEvent::Stop => {
-                                // Stop 事件：记录时间，但保持 Working 状态
-                                // 延迟判断是否真的需要用户输入
-                                status.stop_received_at = Some(Instant::now());
-                                // 保持当前状态（如果是 Working 就保持 Working）
-                                if status.state == CliState::Offline {
-                                    CliState::Working
-                                } else {
-                                    status.state
-                                }
-                            }
-                            CliEvent::IdlePrompt => {
-                                status.stop_received_at = None;
-                                CliState::Idle
-                            }
-                            CliEvent::PermissionPrompt => {
-                                // 权限提示需要立即响应
-                                status.stop_received_at = None;
-                                CliState::WaitingInput
-                            }
-                        };
-                        status.state = new_state;
-
-                        // 保存当前 CLI 的信息用于回调
-                        let current_pid = status.pid;
-                        let current_cwd = status.cwd.clone();
-                        let current_cli = status.cli_name.clone();
-
-                        drop(states_guard);
-
-                        // 计算聚合状态
-                        let aggregate_state = Self::calculate_aggregate_state(&states);
-                        let state_changed = aggregate_state != last_aggregate_state;
-
-                        if state_changed {
-                            last_aggregate_state = aggregate_state;
-                        }
-
-                        // 每次收到事件都通知（用于更新菜单），但标记是否需要通知/置顶
-                        on_state_change(StateChangeEvent {
-                            state: aggregate_state,
-                            pid: current_pid,
-                            cwd: current_cwd,
-                            cli_name: current_cli,
-                            state_changed,
-                        });
-                    }
-                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                        // 超时检查
-                        let mut states_guard = states.lock().unwrap();
-                        let mut state_updated = false;
-
-                        for status in states_guard.values_mut() {
-                            // 检查 Stop 延迟：如果收到 Stop 超过 3 秒没有新事件，转为 WaitingInput
-                            if let Some(stop_time) = status.stop_received_at {
-                                if stop_time.elapsed() > Duration::from_secs(3) {
-                                    status.state = CliState::WaitingInput;
-                                    status.stop_received_at = None;
-                                    state_updated = true;
-                                }
-                            }
-
-                            // 将长时间 WaitingInput 的状态转为 Idle
-                            if status.state == CliState::WaitingInput
-                                && status.last_update.elapsed() > timeout * 6
-                            {
-                                status.state = CliState::Idle;
-                                state_updated = true;
-                            }
-                        }
-                        drop(states_guard);
-
-                        // 检查聚合状态是否变化
-                        let aggregate_state = Self::calculate_aggregate_state(&states);
-                        if aggregate_state != last_aggregate_state || state_updated {
-                            let changed = aggregate_state != last_aggregate_state;
-                            if changed {
-                                last_aggregate_state = aggregate_state;
-                            }
-                            on_state_change(StateChangeEvent {
-                                state: aggregate_state,
-                                pid: None,
-                                cwd: None,
-                                cli_name: String::new(),
-                                state_changed: changed,
-                            });
-                        }
-                    }
-                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-                        eprintln!("State manager channel disconnected");
-                        break;
-                    }
+        let states = self.cli_states;
+        let clock = self.clock;
+        let mut machine = Machine::new(self.timings);
+
+        std::thread::spawn(move || loop {
+            // 非阻塞接收消息，超时 1 秒后做一次定时检查
+            let input = match receiver.recv_timeout(Duration::from_secs(1)) {
+                Ok(msg) => Input::Message(msg),
+                Err(RecvTimeoutError::Timeout) => Input::Tick,
+                Err(RecvTimeoutError::Disconnected) => {
+                    eprintln!("State manager channel disconnected");
+                    break;
                 }
+            };
+
+            let effects = {
+                let mut states_guard = states.lock().unwrap();
+                machine.step(&mut states_guard, input, clock.now())
+            };
+            for effect in effects {
+                on_effect(effect);
             }
         });
     }
+}
 
-    /// 计算聚合状态（用于托盘图标）
-    fn calculate_aggregate_state(states: &Arc<Mutex<HashMap<String, CliStatus>>>) -> CliState {
-        let states_guard = states.lock().unwrap();
-
-        if states_guard.is_empty() {
-            return CliState::Offline;
-        }
-
-        // 优先级：WaitingInput > Working > Idle > Offline
-        let mut has_working = false;
-        let mut has_waiting = false;
-        let mut has_idle = false;
+/// 根据所有 CLI 的状态计算聚合状态
+pub fn aggregate_state(states: &HashMap<String, CliStatus>) -> CliState {
+    if states.is_empty() {
+        return CliState::Offline;
+    }
 
-        for status in states_guard.values() {
-            match status.state {
-                CliState::WaitingInput => has_waiting = true,
-                CliState::Working => has_working = true,
-                CliState::Idle => has_idle = true,
-                CliState::Offline => {}
-            }
+    // 优先级：WaitingInput > Stalled > Working > Idle > Offline
+    let mut has_working = false;
+    let mut has_waiting = false;
+    let mut has_stalled = false;
+    let mut has_idle = false;
+
+    for status in states.values() {
+        match status.state {
+            CliState::WaitingInput => has_waiting = true,
+            CliState::Stalled => has_stalled = true,
+            CliState::Working => has_working = true,
+            CliState::Idle => has_idle = true,
+            CliState::Offline => {}
         }
+    }
 
-        if has_waiting {
-            CliState::WaitingInput
-        } else if has_working {
-            CliState::Working
-        } else if has_idle {
-            CliState::Idle
-        } else {
-            CliState::Offline
-        }
+    if has_waiting {
+        CliState::WaitingInput
+    } else if has_stalled {
+        CliState::Stalled
+    } else if has_working {
+        CliState::Working
+    } else if has_idle {
+        CliState::Idle
+    } else {
+        CliState::Offline
     }
 }
 
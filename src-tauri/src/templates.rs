@@ -0,0 +1,200 @@
+//! 通知模板
+//!
+//! 每种通知的标题和正文都可以在配置中自定义，支持占位符：
+//! - 会话相关（CLI 等待、卡住）：{cli}、{project}、{cwd}、{label}、{state}、{waited}、{session_id}
+//! - 久坐提醒：{minutes}、{reset_minutes}（离开多久后自动重置计时）
+//! - 发现新版本：{version}
+//! - 免打扰汇总：{count}、{items}
+//!
+//! 没有配置的部分使用当前语言的内置文案，内置文案同样按占位符渲染。
+
+use crate::cli_registry::CliRegistry;
+use crate::i18n::{format_duration, get_strings, Language};
+use crate::ipc_server::CliEvent;
+use crate::state_manager::{CliState, CliStatus};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// 模板对应的通知种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateKind {
+    /// 开始等待时的桌面通知
+    CliWaiting,
+    /// 等待提醒阶梯中推送到其他渠道的通知
+    CliWaitingPush,
+    CliStalled,
+    SittingReminder,
+    Update,
+    QuietSummary,
+}
+
+/// 用户自定义的模板，没有填写的部分使用内置文案
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotificationTemplate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+/// 渲染模板用的变量
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVars {
+    values: Vec<(&'static str, String)>,
+}
+
+impl TemplateVars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.values.push((name, value.into()));
+        self
+    }
+
+    /// 会话相关的变量，waited 为已等待（或没有活动）的时长
+    pub fn session(
+        lang: Language,
+        status: &CliStatus,
+        registry: &CliRegistry,
+        waited: Duration,
+    ) -> Self {
+        let cli = registry
+            .get(&status.cli_name)
+            .map(|def| def.display_name.clone())
+            .unwrap_or_else(|| status.cli_name.clone());
+        let project = status
+            .cwd
+            .as_deref()
+            .map(|cwd| {
+                Path::new(cwd)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(cwd)
+                    .to_string()
+            })
+            .unwrap_or_default();
+
+        Self::new()
+            .set("cli", cli)
+            .set("project", project)
+            .set("cwd", status.cwd.clone().unwrap_or_default())
+            .set("label", registry.menu_label(status))
+            .set("state", state_phrase(lang, status))
+            .set("waited", format_duration(waited.as_secs()))
+            .set("session_id", status.session_id.clone().unwrap_or_default())
+    }
+
+    /// 替换模板中的 {name}，不认识的占位符原样保留
+    /// 只扫描一遍模板，变量值中的 {name} 不会再被展开
+    pub fn render(&self, template: &str) -> String {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            output.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            // 名称到下一个 '}' 为止，中间又出现 '{' 时不是占位符
+            let value = after
+                .find(['{', '}'])
+                .filter(|&close| after[close..].starts_with('}'))
+                .and_then(|close| self.get(&after[..close]).map(|value| (value, close)));
+            match value {
+                Some((value, close)) => {
+                    output.push_str(value);
+                    rest = &after[close + 1..];
+                }
+                None => {
+                    output.push('{');
+                    rest = after;
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// {state} 的文字，等待授权时单独说明
+fn state_phrase(lang: Language, status: &CliStatus) -> &'static str {
+    let s = get_strings(lang);
+    match status.state {
+        CliState::WaitingInput if status.last_event == Some(CliEvent::PermissionPrompt) => {
+            s.state_permission
+        }
+        CliState::WaitingInput => s.state_waiting,
+        CliState::Idle => s.state_idle,
+        CliState::Stalled => s.state_stalled,
+        CliState::Working => s.state_working,
+        CliState::Offline => s.state_offline,
+    }
+}
+
+/// 按模板渲染标题和正文，template 为 None 或缺少某部分时使用内置文案
+pub fn render(
+    template: Option<&NotificationTemplate>,
+    default_title: &str,
+    default_body: &str,
+    vars: &TemplateVars,
+) -> (String, String) {
+    let title = template
+        .and_then(|t| t.title.as_deref())
+        .unwrap_or(default_title);
+    let body = template
+        .and_then(|t| t.body.as_deref())
+        .unwrap_or(default_body);
+    (vars.render(title), vars.render(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_replaces_known_placeholders() {
+        let vars = TemplateVars::new().set("cli", "Claude").set("waited", "5m");
+        assert_eq!(
+            vars.render("{cli} waited {waited}, {cli}!"),
+            "Claude waited 5m, Claude!"
+        );
+    }
+
+    #[test]
+    fn render_keeps_unknown_and_unbalanced_braces() {
+        let vars = TemplateVars::new().set("cli", "Claude");
+        assert_eq!(vars.render("{other} {cli"), "{other} {cli");
+        assert_eq!(vars.render("{{cli}}"), "{Claude}");
+        assert_eq!(vars.render("}{cli}{"), "}Claude{");
+    }
+
+    #[test]
+    fn render_does_not_expand_values() {
+        // 项目目录名里恰好包含占位符时原样输出
+        let vars = TemplateVars::new()
+            .set("project", "{cli}-tools")
+            .set("cli", "Claude");
+        assert_eq!(vars.render("{project} / {cli}"), "{cli}-tools / Claude");
+    }
+
+    #[test]
+    fn render_uses_defaults_for_missing_parts() {
+        let template = NotificationTemplate {
+            title: Some("[{cli}]".to_string()),
+            body: None,
+        };
+        let vars = TemplateVars::new().set("cli", "Codex");
+        assert_eq!(
+            render(Some(&template), "default", "{cli} is waiting", &vars),
+            ("[Codex]".to_string(), "Codex is waiting".to_string())
+        );
+    }
+}
@@ -0,0 +1,982 @@
+//! 托盘界面：菜单、图标、设置项和 Tauri 命令
+//!
+//! 只在启用 tray feature 时编译，无界面模式（headless）不依赖 Tauri。
+
+use crate::activity_monitor::ActivityMonitor;
+use crate::analytics::LatencyReport;
+use crate::channels::{NotificationChannel, NotifyEvent};
+use crate::config::ConfigManager;
+use crate::engine::{Engine, Frontend};
+use crate::i18n::{
+    format_interval, format_latency_summary, format_sitting_time, format_snooze,
+    format_snoozed_until, format_version, get_strings,
+};
+use crate::ipc_server::ReminderAction;
+use crate::process_monitor::ProcessInfo;
+use crate::report::ReportPeriod;
+use crate::state_manager::{CliState, CliStatus, SessionSnapshot, StateChangeEvent};
+use crate::templates::{TemplateKind, TemplateVars};
+use std::sync::{Arc, Mutex};
+use tauri::{
+    image::Image,
+    menu::{CheckMenuItem, IconMenuItem, Menu, MenuItem, PredefinedMenuItem},
+    tray::TrayIconBuilder,
+};
+use tauri_plugin_autostart::ManagerExt;
+
+// 内嵌四种状态的图标
+const ICON_GRAY: &[u8] = include_bytes!("../icons/tray_gray.png");
+const ICON_GREEN: &[u8] = include_bytes!("../icons/tray_green.png");
+const ICON_RED: &[u8] = include_bytes!("../icons/tray_red.png");
+const ICON_YELLOW: &[u8] = include_bytes!("../icons/tray_yellow.png");
+
+#[derive(Clone, Copy, PartialEq)]
+enum TrayState {
+    Gray,  // 无CLI运行
+    Green, // CLI运行中，用户交互中
+    Red,   // CLI等待用户输入
+    Yellow, // CLI可能卡住了
+}
+
+impl From<CliState> for TrayState {
+    fn from(state: CliState) -> Self {
+        match state {
+            CliState::Working => TrayState::Green,
+            CliState::WaitingInput => TrayState::Red,
+            CliState::Idle => TrayState::Red, // Idle 也显示红色提醒用户
+            CliState::Stalled => TrayState::Yellow,
+            CliState::Offline => TrayState::Gray,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    engine: Engine,
+    tray_state: Arc<Mutex<TrayState>>,
+}
+
+fn get_tray_icon(state: TrayState) -> Image<'static> {
+    let data = match state {
+        TrayState::Gray => ICON_GRAY,
+        TrayState::Green => ICON_GREEN,
+        TrayState::Red => ICON_RED,
+        TrayState::Yellow => ICON_YELLOW,
+    };
+    Image::from_bytes(data).expect("Failed to load tray icon")
+}
+
+fn format_title(minutes: u32, _activity_monitor: Option<&ActivityMonitor>) -> String {
+    if minutes >= 60 {
+        format!("{}h{}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[tauri::command]
+fn get_cli_processes(state: tauri::State<'_, AppState>) -> Vec<ProcessInfo> {
+    crate::process_monitor::get_cli_processes(&state.engine.config.get_cli_registry())
+}
+
+/// 所有会话的详情，包括最近一次采样的资源占用
+#[tauri::command]
+fn get_sessions(state: tauri::State<'_, AppState>) -> Vec<SessionSnapshot> {
+    let now = state.engine.clock.now();
+    let states = state.engine.cli_states.lock().unwrap();
+    let mut sessions: Vec<SessionSnapshot> =
+        states.iter().map(|(key, s)| s.snapshot(key, now)).collect();
+    drop(states);
+    sessions.sort_by(|a, b| a.key.cmp(&b.key));
+    sessions
+}
+
+/// 最近几天（默认 7 天）的响应延迟统计
+#[tauri::command]
+fn get_latency_report(state: tauri::State<'_, AppState>, days: Option<u32>) -> LatencyReport {
+    state.engine.latency_report(days.unwrap_or(7).clamp(1, 90))
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // 创建后台引擎（IPC、状态管理、进程检测、久坐计时）
+    let engine = Engine::new(Arc::new(ConfigManager::new()));
+
+    let state = AppState {
+        engine,
+        tray_state: Arc::new(Mutex::new(TrayState::Gray)),
+    };
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::AppleScript,
+            Some(vec![]),
+        ))
+        .manage(state.clone())
+        .invoke_handler(tauri::generate_handler![
+            get_cli_processes,
+            get_sessions,
+            get_latency_report
+        ])
+        .setup(move |app| {
+            let handle = app.handle().clone();
+            let state_clone = state.clone();
+
+            // 加载配置
+            state.engine.config.load(&handle);
+
+            // 初始检测CLI状态，并添加到状态列表
+            let initial_tray_state: TrayState = state.engine.scan_initial_processes().into();
+            *state.tray_state.lock().unwrap() = initial_tray_state;
+
+            let show_time = state.engine.config.get_show_time();
+            let cli_states_snapshot: Vec<CliStatus> = state
+                .engine
+                .cli_states
+                .lock()
+                .unwrap()
+                .values()
+                .cloned()
+                .collect();
+            let menu = build_menu(
+                &handle,
+                0,
+                initial_tray_state,
+                &cli_states_snapshot,
+                &state.engine,
+            );
+
+            let initial_title = if show_time {
+                Some("0m".to_string())
+            } else {
+                None
+            };
+
+            let _tray = TrayIconBuilder::with_id("main")
+                .icon(get_tray_icon(initial_tray_state))
+                .title(initial_title.as_deref().unwrap_or(""))
+                .menu(&menu)
+                .show_menu_on_left_click(true)
+                .on_menu_event(move |app, event| {
+                    match event.id.as_ref() {
+                        "reset" => {
+                            state_clone.engine.reset_timer();
+                            if let Some(tray) = app.tray_by_id("main") {
+                                let current_state = *state_clone.tray_state.lock().unwrap();
+                                let show_time = state_clone.engine.config.get_show_time();
+                                if show_time {
+                                    let _ = tray.set_title(Some("0m"));
+                                }
+                                let cli_states_snapshot: Vec<CliStatus> = state_clone
+                                    .engine
+                                    .cli_states
+                                    .lock()
+                                    .unwrap()
+                                    .values()
+                                    .cloned()
+                                    .collect();
+                                let _ = tray.set_menu(Some(build_menu(
+                                    app,
+                                    0,
+                                    current_state,
+                                    &cli_states_snapshot,
+                                    &state_clone.engine,
+                                )));
+                            }
+                        }
+                        "toggle_time" => {
+                            let new_show_time = state_clone.engine.config.toggle_show_time();
+                            state_clone.engine.config.save(app);
+                            if let Some(tray) = app.tray_by_id("main") {
+                                let minutes = *state_clone.engine.sitting_minutes.lock().unwrap();
+                                let current_state = *state_clone.tray_state.lock().unwrap();
+                                if new_show_time {
+                                    let _ = tray.set_title(Some(&format_title(
+                                        minutes,
+                                        Some(&state_clone.engine.activity_monitor),
+                                    )));
+                                } else {
+                                    let _ = tray.set_title(Some(""));
+                                }
+                                let cli_states_snapshot: Vec<CliStatus> = state_clone
+                                    .engine
+                                    .cli_states
+                                    .lock()
+                                    .unwrap()
+                                    .values()
+                                    .cloned()
+                                    .collect();
+                                let _ = tray.set_menu(Some(build_menu(
+                                    app,
+                                    minutes,
+                                    current_state,
+                                    &cli_states_snapshot,
+                                    &state_clone.engine,
+                                )));
+                            }
+                        }
+                        "toggle_sound" => {
+                            let new_enabled = state_clone.engine.config.toggle_sound();
+                            state_clone.engine.config.save(app);
+
+                            // 如果开启，请求通知权限并发送测试通知
+                            if new_enabled {
+                                let lang = state_clone.engine.config.get_language();
+                                // 先请求权限
+                                match crate::notification::request_notification_permission(app) {
+                                    Ok(granted) => {
+                                        println!("Notification permission: {}", if granted { "granted" } else { "denied" });
+                                        if granted {
+                                            let _ = crate::notification::notify_sound_enabled(
+                                                app, lang,
+                                            );
+                                        }
+                                    }
+                                    Err(e) => {
+                                        println!("Request notification permission failed: {}", e);
+                                        // 即使请求失败也尝试发送通知（可能会触发系统权限弹窗）
+                                        let _ =
+                                            crate::notification::notify_sound_enabled(app, lang);
+                                    }
+                                }
+                            }
+
+                            if let Some(tray) = app.tray_by_id("main") {
+                                let minutes = *state_clone.engine.sitting_minutes.lock().unwrap();
+                                let current_state = *state_clone.tray_state.lock().unwrap();
+                                let cli_states_snapshot: Vec<CliStatus> = state_clone
+                                    .engine
+                                    .cli_states
+                                    .lock()
+                                    .unwrap()
+                                    .values()
+                                    .cloned()
+                                    .collect();
+                                let _ = tray.set_menu(Some(build_menu(
+                                    app,
+                                    minutes,
+                                    current_state,
+                                    &cli_states_snapshot,
+                                    &state_clone.engine,
+                                )));
+                            }
+                        }
+                        "toggle_front" => {
+                            let new_enabled =
+                                state_clone.engine.config.toggle_auto_bring_to_front();
+                            state_clone.engine.config.save(app);
+
+                            // 如果开启，测试置顶功能
+                            if new_enabled {
+                                let _ = crate::window_manager::bring_terminal_to_front();
+                            }
+
+                            if let Some(tray) = app.tray_by_id("main") {
+                                let minutes = *state_clone.engine.sitting_minutes.lock().unwrap();
+                                let current_state = *state_clone.tray_state.lock().unwrap();
+                                let cli_states_snapshot: Vec<CliStatus> = state_clone
+                                    .engine
+                                    .cli_states
+                                    .lock()
+                                    .unwrap()
+                                    .values()
+                                    .cloned()
+                                    .collect();
+                                let _ = tray.set_menu(Some(build_menu(
+                                    app,
+                                    minutes,
+                                    current_state,
+                                    &cli_states_snapshot,
+                                    &state_clone.engine,
+                                )));
+                            }
+                        }
+                        "toggle_auto_start" => {
+                            let autolaunch = app.autolaunch();
+                            let is_enabled = autolaunch.is_enabled().unwrap_or(false);
+                            if is_enabled {
+                                let _ = autolaunch.disable();
+                            } else {
+                                let _ = autolaunch.enable();
+                            }
+
+                            if let Some(tray) = app.tray_by_id("main") {
+                                let minutes = *state_clone.engine.sitting_minutes.lock().unwrap();
+                                let current_state = *state_clone.tray_state.lock().unwrap();
+                                let cli_states_snapshot: Vec<CliStatus> = state_clone
+                                    .engine
+                                    .cli_states
+                                    .lock()
+                                    .unwrap()
+                                    .values()
+                                    .cloned()
+                                    .collect();
+                                let _ = tray.set_menu(Some(build_menu(
+                                    app,
+                                    minutes,
+                                    current_state,
+                                    &cli_states_snapshot,
+                                    &state_clone.engine,
+                                )));
+                            }
+                        }
+                        "toggle_sitting_reminder" => {
+                            let new_enabled = state_clone.engine.config.toggle_sitting_reminder();
+                            state_clone.engine.config.save(app);
+
+                            // 如果开启，检查权限并启动活动监听器
+                            if new_enabled {
+                                // 检查辅助功能权限
+                                if !crate::activity_monitor::check_accessibility_permission() {
+                                    // 没有权限，请求权限
+                                    crate::activity_monitor::request_accessibility_permission();
+                                }
+                                state_clone.engine.activity_monitor.start();
+                            }
+
+                            if let Some(tray) = app.tray_by_id("main") {
+                                let minutes = *state_clone.engine.sitting_minutes.lock().unwrap();
+                                let current_state = *state_clone.tray_state.lock().unwrap();
+                                let cli_states_snapshot: Vec<CliStatus> = state_clone
+                                    .engine
+                                    .cli_states
+                                    .lock()
+                                    .unwrap()
+                                    .values()
+                                    .cloned()
+                                    .collect();
+                                let _ = tray.set_menu(Some(build_menu(
+                                    app,
+                                    minutes,
+                                    current_state,
+                                    &cli_states_snapshot,
+                                    &state_clone.engine,
+                                )));
+                            }
+                        }
+                        "cycle_interval" => {
+                            let _new_interval =
+                                state_clone.engine.config.cycle_sitting_reminder_interval();
+                            state_clone.engine.config.save(app);
+
+                            if let Some(tray) = app.tray_by_id("main") {
+                                let minutes = *state_clone.engine.sitting_minutes.lock().unwrap();
+                                let current_state = *state_clone.tray_state.lock().unwrap();
+                                let cli_states_snapshot: Vec<CliStatus> = state_clone
+                                    .engine
+                                    .cli_states
+                                    .lock()
+                                    .unwrap()
+                                    .values()
+                                    .cloned()
+                                    .collect();
+                                let _ = tray.set_menu(Some(build_menu(
+                                    app,
+                                    minutes,
+                                    current_state,
+                                    &cli_states_snapshot,
+                                    &state_clone.engine,
+                                )));
+                            }
+                        }
+                        "toggle_lang" => {
+                            let _new_lang = state_clone.engine.config.toggle_language();
+                            state_clone.engine.config.save(app);
+
+                            if let Some(tray) = app.tray_by_id("main") {
+                                let minutes = *state_clone.engine.sitting_minutes.lock().unwrap();
+                                let current_state = *state_clone.tray_state.lock().unwrap();
+                                let cli_states_snapshot: Vec<CliStatus> = state_clone
+                                    .engine
+                                    .cli_states
+                                    .lock()
+                                    .unwrap()
+                                    .values()
+                                    .cloned()
+                                    .collect();
+                                let _ = tray.set_menu(Some(build_menu(
+                                    app,
+                                    minutes,
+                                    current_state,
+                                    &cli_states_snapshot,
+                                    &state_clone.engine,
+                                )));
+                            }
+                        }
+                        "check_update" => {
+                            let app_handle = app.clone();
+                            let state_for_update = state_clone.clone();
+                            tauri::async_runtime::spawn(async move {
+                                let lang = state_for_update.engine.config.get_language();
+                                let s = get_strings(lang);
+
+                                // 先发送检查中的通知
+                                let _ = crate::notification::send_system_notification(
+                                    &app_handle,
+                                    s.app_name,
+                                    s.checking_update,
+                                    false,
+                                );
+
+                                match crate::updater::check_for_update(&app_handle).await {
+                                    Ok(Some(version)) => {
+                                        // 有新版本，发送通知并开始下载
+                                        // 发到所有订阅了 update 事件的渠道
+                                        let engine = &state_for_update.engine;
+                                        engine.notify(
+                                            Arc::new(crate::notification::TauriChannel {
+                                                app: app_handle.clone(),
+                                            }),
+                                            engine.templated(
+                                                TemplateKind::Update,
+                                                NotifyEvent::Update,
+                                                (s.app_name, s.update_available),
+                                                &TemplateVars::new()
+                                                    .set("version", version.as_str()),
+                                                false,
+                                            ),
+                                        );
+
+                                        // 下载并安装
+                                        let _ = crate::notification::send_system_notification(
+                                            &app_handle,
+                                            s.app_name,
+                                            s.downloading,
+                                            false,
+                                        );
+                                        match crate::updater::download_and_install(&app_handle)
+                                            .await
+                                        {
+                                            Ok(()) => {
+                                                // 安装成功，提示重启
+                                                let _ =
+                                                    crate::notification::send_system_notification(
+                                                        &app_handle,
+                                                        s.app_name,
+                                                        s.install_restart,
+                                                        false,
+                                                    );
+                                                // 重启应用
+                                                app_handle.restart();
+                                            }
+                                            Err(e) => {
+                                                println!("Update install error: {}", e);
+                                                let _ =
+                                                    crate::notification::send_system_notification(
+                                                        &app_handle,
+                                                        s.app_name,
+                                                        s.update_error,
+                                                        false,
+                                                    );
+                                            }
+                                        }
+                                    }
+                                    Ok(None) => {
+                                        // 已是最新版本
+                                        let _ = crate::notification::send_system_notification(
+                                            &app_handle,
+                                            s.app_name,
+                                            s.no_update,
+                                            false,
+                                        );
+                                    }
+                                    Err(e) => {
+                                        // 检查失败
+                                        println!("Update check error: {}", e);
+                                        let _ = crate::notification::send_system_notification(
+                                            &app_handle,
+                                            s.app_name,
+                                            s.update_error,
+                                            false,
+                                        );
+                                    }
+                                }
+                            });
+                        }
+                        "response_latency" => {
+                            let lang = state_clone.engine.config.get_language();
+                            let s = get_strings(lang);
+                            let report = state_clone.engine.latency_report(1);
+                            let today = report
+                                .days
+                                .first()
+                                .map(|d| d.overall.clone())
+                                .unwrap_or_default();
+                            let body = format_latency_summary(
+                                lang,
+                                today.count,
+                                today.median_secs,
+                                today.p90_secs,
+                            );
+                            let _ = crate::notification::send_system_notification(
+                                app,
+                                s.response_latency,
+                                &body,
+                                false,
+                            );
+                        }
+                        "daily_report" | "weekly_report" => {
+                            let period = if event.id.as_ref() == "daily_report" {
+                                ReportPeriod::Daily
+                            } else {
+                                ReportPeriod::Weekly
+                            };
+                            match state_clone
+                                .engine
+                                .write_report(period, state_clone.engine.today())
+                            {
+                                Ok(paths) => crate::report::open_report(&paths[0]),
+                                Err(e) => {
+                                    eprintln!("Report: {}", e);
+                                    let s = get_strings(state_clone.engine.config.get_language());
+                                    let _ = crate::notification::send_system_notification(
+                                        app,
+                                        s.app_name,
+                                        s.report_failed,
+                                        false,
+                                    );
+                                }
+                            }
+                        }
+                        "clear_stale_sessions" => {
+                            state_clone.engine.clear_stale_sessions();
+                            refresh_tray(app, &state_clone);
+                        }
+                        "toggle_stall_notify" => {
+                            state_clone.engine.config.toggle_stall_notify();
+                            state_clone.engine.config.save(app);
+                            refresh_tray(app, &state_clone);
+                        }
+                        "snooze" => {
+                            let now = state_clone.engine.clock.unix_secs();
+                            if state_clone.engine.config.get_snooze_until(now).is_some() {
+                                state_clone.engine.config.cancel_snooze();
+                            } else {
+                                state_clone.engine.config.snooze(now);
+                            }
+                            state_clone.engine.config.save(app);
+                            refresh_tray(app, &state_clone);
+                        }
+                        "toggle_meeting_mode" => {
+                            state_clone.engine.config.toggle_meeting_mode();
+                            state_clone.engine.config.save(app);
+                            refresh_tray(app, &state_clone);
+                        }
+                        "reminder_snooze" | "reminder_took_break" | "reminder_skip" => {
+                            let action = match event.id.as_ref() {
+                                "reminder_snooze" => ReminderAction::Snooze,
+                                "reminder_took_break" => ReminderAction::TookBreak,
+                                _ => ReminderAction::Skip,
+                            };
+                            if let Err(e) = state_clone.engine.reminder_action(action) {
+                                println!("Reminder action ignored: {}", e);
+                            }
+                            refresh_tray(app, &state_clone);
+                        }
+                        "toggle_auto_report" => {
+                            state_clone.engine.config.toggle_auto_daily_report();
+                            state_clone.engine.config.save(app);
+                            refresh_tray(app, &state_clone);
+                        }
+                        "quit" => {
+                            // 清理 IPC socket
+                            crate::ipc_server::cleanup();
+                            app.exit(0);
+                        }
+                        _ => {}
+                    }
+                })
+                .build(app)?;
+
+            // 启动后台引擎，托盘负责界面输出
+            state.engine.start(Arc::new(TrayFrontend {
+                app: handle.clone(),
+                state: state.clone(),
+                shown_usage: Mutex::new(Vec::new()),
+            }));
+
+            Ok(())
+        })
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+/// 托盘模式下的引擎输出
+struct TrayFrontend {
+    app: tauri::AppHandle,
+    state: AppState,
+    /// 上次采样后菜单中显示的资源占用，文字没有变化时不重建菜单
+    shown_usage: Mutex<Vec<(String, String)>>,
+}
+
+impl Frontend for TrayFrontend {
+    fn desktop_channel(&self) -> Arc<dyn NotificationChannel> {
+        Arc::new(crate::notification::TauriChannel {
+            app: self.app.clone(),
+        })
+    }
+
+    fn state_changed(&self, event: &StateChangeEvent) {
+        let new_tray_state: TrayState = event.state.into();
+        *self.state.tray_state.lock().unwrap() = new_tray_state;
+
+        // 每次收到事件都更新图标和菜单（确保 CLI 列表实时更新）
+        if let Some(tray) = self.app.tray_by_id("main") {
+            let _ = tray.set_icon(Some(get_tray_icon(new_tray_state)));
+        }
+        refresh_tray(&self.app, &self.state);
+    }
+
+    fn sitting_tick(&self, _minutes: u32) {
+        refresh_tray(&self.app, &self.state);
+    }
+
+    fn timer_reset(&self) {
+        refresh_tray(&self.app, &self.state);
+    }
+
+    fn resources_sampled(&self) {
+        let mut usage: Vec<(String, String)> = self
+            .state
+            .engine
+            .cli_states
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, status)| status.state != CliState::Offline)
+            .filter_map(|(key, status)| {
+                let text = crate::resource_monitor::format_usage(status.resources.as_ref()?);
+                Some((key.clone(), text))
+            })
+            .collect();
+        usage.sort();
+
+        let mut shown = self.shown_usage.lock().unwrap();
+        if *shown != usage {
+            *shown = usage;
+            drop(shown);
+            refresh_tray(&self.app, &self.state);
+        }
+    }
+
+    fn quiet_changed(&self) {
+        refresh_tray(&self.app, &self.state);
+    }
+
+    fn reminder_resolved(&self) {
+        refresh_tray(&self.app, &self.state);
+    }
+}
+
+/// 按当前状态刷新托盘标题和菜单
+fn refresh_tray(app: &tauri::AppHandle, state: &AppState) {
+    if let Some(tray) = app.tray_by_id("main") {
+        let minutes = *state.engine.sitting_minutes.lock().unwrap();
+        if state.engine.config.get_show_time() {
+            let _ = tray.set_title(Some(&format_title(
+                minutes,
+                Some(&state.engine.activity_monitor),
+            )));
+        }
+        let current_state = *state.tray_state.lock().unwrap();
+        let cli_states_snapshot: Vec<CliStatus> = state
+            .engine
+            .cli_states
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        let _ = tray.set_menu(Some(build_menu(
+            app,
+            minutes,
+            current_state,
+            &cli_states_snapshot,
+            &state.engine,
+        )));
+    }
+}
+
+fn build_menu<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    minutes: u32,
+    _tray_state: TrayState,
+    cli_states: &[CliStatus],
+    engine: &Engine,
+) -> Menu<R> {
+    let config = &engine.config;
+    let menu = Menu::new(app).unwrap();
+    let lang = config.get_language();
+    let s = get_strings(lang);
+
+    // 显示各个 CLI 的状态
+    let active_clis: Vec<&CliStatus> = cli_states
+        .iter()
+        .filter(|s| s.state != CliState::Offline)
+        .collect();
+
+    if active_clis.is_empty() {
+        // 无 CLI 运行
+        let status = MenuItem::new(app, s.no_cli_running, false, None::<&str>).unwrap();
+        let _ = menu.append(&status);
+    } else {
+        // 显示每个 CLI 的状态
+        let registry = config.get_cli_registry();
+        for cli_status in &active_clis {
+            let icon_data = match cli_status.state {
+                CliState::Working => ICON_GREEN,
+                CliState::WaitingInput => ICON_RED,
+                CliState::Idle => ICON_GRAY,
+                CliState::Stalled => ICON_YELLOW,
+                CliState::Offline => continue,
+            };
+            let icon = Image::from_bytes(icon_data).ok();
+
+            // 有资源采样时附在名称后面，如 "Claude - my-project  (12% · 340 MB · +3)"
+            let mut label = registry.menu_label(cli_status);
+            if cli_status.state == CliState::Stalled {
+                label = format!("{} [{}]", label, s.stalled);
+            }
+            if let Some(usage) = &cli_status.resources {
+                label = format!(
+                    "{}  ({})",
+                    label,
+                    crate::resource_monitor::format_usage(usage)
+                );
+            }
+            let cli_item = IconMenuItem::new(
+                app,
+                label,
+                false,
+                icon,
+                None::<&str>,
+            )
+            .unwrap();
+            let _ = menu.append(&cli_item);
+        }
+    }
+
+    // 清理已下线、进程已退出的会话
+    let clear_stale = MenuItem::with_id(
+        app,
+        "clear_stale_sessions",
+        s.clear_stale_sessions,
+        !cli_states.is_empty(),
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&clear_stale);
+
+    // 分隔线
+    let separator1 = PredefinedMenuItem::separator(app).unwrap();
+    let _ = menu.append(&separator1);
+
+    // 久坐时间
+    let time_str = format_sitting_time(lang, minutes);
+    let time_item = MenuItem::new(app, time_str, false, None::<&str>).unwrap();
+    let _ = menu.append(&time_item);
+
+    // 今日响应时间（点击后计算并以通知显示）
+    let history_enabled = config.get_history_enabled();
+    let latency_item = MenuItem::with_id(
+        app,
+        "response_latency",
+        s.response_latency,
+        history_enabled,
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&latency_item);
+
+    // 日报 / 周报
+    let daily_report = MenuItem::with_id(
+        app,
+        "daily_report",
+        s.daily_report,
+        history_enabled,
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&daily_report);
+    let weekly_report = MenuItem::with_id(
+        app,
+        "weekly_report",
+        s.weekly_report,
+        history_enabled,
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&weekly_report);
+    let toggle_auto_report = CheckMenuItem::with_id(
+        app,
+        "toggle_auto_report",
+        s.auto_daily_report,
+        history_enabled,
+        config.get_auto_daily_report(),
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&toggle_auto_report);
+
+    // 分隔线
+    let separator2 = PredefinedMenuItem::separator(app).unwrap();
+    let _ = menu.append(&separator2);
+
+    // 设置选项
+    let show_time = config.get_show_time();
+    let toggle_time = CheckMenuItem::with_id(
+        app,
+        "toggle_time",
+        s.show_time,
+        true,
+        show_time,
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&toggle_time);
+
+    let sound_enabled = config.get_sound_enabled();
+    let toggle_sound = CheckMenuItem::with_id(
+        app,
+        "toggle_sound",
+        s.sound_notification,
+        true,
+        sound_enabled,
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&toggle_sound);
+
+    let auto_front = config.get_auto_bring_to_front();
+    let toggle_front = CheckMenuItem::with_id(
+        app,
+        "toggle_front",
+        s.auto_bring_to_front,
+        true,
+        auto_front,
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&toggle_front);
+
+    let toggle_stall_notify = CheckMenuItem::with_id(
+        app,
+        "toggle_stall_notify",
+        s.stall_notify,
+        true,
+        config.get_stall_notify(),
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&toggle_stall_notify);
+
+    // 暂停提醒：未暂停时显示时长，暂停中显示截止时间，再次点击恢复
+    let now = chrono::Local::now().timestamp() as u64;
+    let snooze_label = match config.get_snooze_until(now) {
+        Some(until) => format_snoozed_until(
+            lang,
+            &crate::quiet_hours::local_datetime(until)
+                .format("%H:%M")
+                .to_string(),
+        ),
+        None => format_snooze(lang, config.get_snooze_minutes()),
+    };
+    let snooze = MenuItem::with_id(app, "snooze", snooze_label, true, None::<&str>).unwrap();
+    let _ = menu.append(&snooze);
+
+    let toggle_meeting_mode = CheckMenuItem::with_id(
+        app,
+        "toggle_meeting_mode",
+        s.meeting_mode,
+        true,
+        config.get_meeting_mode(),
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&toggle_meeting_mode);
+
+    // 开机自动启动
+    let auto_start_enabled = app.autolaunch().is_enabled().unwrap_or(false);
+    let toggle_auto_start = CheckMenuItem::with_id(
+        app,
+        "toggle_auto_start",
+        s.auto_start,
+        true,
+        auto_start_enabled,
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&toggle_auto_start);
+
+    let sitting_reminder_enabled = config.get_sitting_reminder_enabled();
+    let toggle_sitting_reminder = CheckMenuItem::with_id(
+        app,
+        "toggle_sitting_reminder",
+        s.smart_sitting_reminder,
+        true,
+        sitting_reminder_enabled,
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&toggle_sitting_reminder);
+
+    let interval = config.get_sitting_reminder_interval();
+    let interval_label = format_interval(lang, interval);
+    let cycle_interval = MenuItem::with_id(
+        app,
+        "cycle_interval",
+        interval_label,
+        sitting_reminder_enabled, // 只有开启久坐提醒时才可点击
+        None::<&str>,
+    )
+    .unwrap();
+    let _ = menu.append(&cycle_interval);
+
+    // 久坐提醒等待回应时，也可以在菜单中回应
+    if engine.reminder_pending() {
+        for (id, label) in [
+            ("reminder_snooze", s.reminder_snooze),
+            ("reminder_took_break", s.reminder_took_break),
+            ("reminder_skip", s.reminder_skip),
+        ] {
+            let item = MenuItem::with_id(app, id, label, true, None::<&str>).unwrap();
+            let _ = menu.append(&item);
+        }
+    }
+
+    // 分隔线
+    let separator3 = PredefinedMenuItem::separator(app).unwrap();
+    let _ = menu.append(&separator3);
+
+    // 语言切换
+    let toggle_lang = MenuItem::with_id(app, "toggle_lang", s.language, true, None::<&str>).unwrap();
+    let _ = menu.append(&toggle_lang);
+
+    // 分隔线
+    let separator4 = PredefinedMenuItem::separator(app).unwrap();
+    let _ = menu.append(&separator4);
+
+    // 版本信息
+    let version = app.package_info().version.to_string();
+    let version_label = format_version(lang, &version);
+    let version_item = MenuItem::new(app, version_label, false, None::<&str>).unwrap();
+    let _ = menu.append(&version_item);
+
+    // 检查更新
+    let check_update = MenuItem::with_id(app, "check_update", s.check_update, true, None::<&str>).unwrap();
+    let _ = menu.append(&check_update);
+
+    // 重置计时
+    let reset = MenuItem::with_id(app, "reset", s.reset_timer, true, None::<&str>).unwrap();
+    let _ = menu.append(&reset);
+
+    // 退出
+    let quit = MenuItem::with_id(app, "quit", s.quit, true, None::<&str>).unwrap();
+    let _ = menu.append(&quit);
+
+    menu
+}
@@ -2,6 +2,7 @@ use tauri::AppHandle;
 use tauri_plugin_updater::UpdaterExt;
 
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub enum UpdateStatus {
     Checking,
     Available { version: String },
@@ -65,6 +66,7 @@ pub async fn download_and_install(app: &AppHandle) -> Result<(), String> {
 }
 
 /// 获取当前版本
+#[allow(dead_code)]
 pub fn get_current_version(app: &AppHandle) -> String {
     app.package_info().version.to_string()
 }
@@ -1,53 +1,27 @@
+use crate::cli_registry::CliRegistry;
+use crate::process_inspector::inspector;
+use crate::process_monitor;
 use std::process::Command;
 
 /// 已知的终端和 IDE 应用
 const TERMINAL_APPS: &[&str] = &["Warp", "iTerm", "iTerm2", "Terminal", "Alacritty", "kitty"];
 const IDE_APPS: &[&str] = &["Cursor", "Code", "Antigravity"];
 
-/// 已知的 CLI 进程名
-const CLI_PROCESS_NAMES: &[&str] = &["claude", "codex", "gemini"];
-
 /// 通过 CWD 查找 CLI 进程的 PID
-/// 因为 hooks 传来的 PID 是脚本进程（已退出），需要通过 CWD 找到真正的 CLI 进程
-fn find_cli_pid_by_cwd(cwd: &str) -> Option<u32> {
+/// 旧版 hook 客户端上报的是脚本自己的 PID（已退出），只能通过 CWD 找到 CLI 进程
+fn find_cli_pid_by_cwd(cwd: &str, registry: &CliRegistry) -> Option<u32> {
     println!("通过 CWD 查找 CLI 进程: {}", cwd);
 
-    for cli_name in CLI_PROCESS_NAMES {
-        // 使用 pgrep 查找进程
-        let output = Command::new("pgrep")
-            .args(["-x", cli_name])
-            .output()
-            .ok()?;
-
-        let pids_str = String::from_utf8_lossy(&output.stdout);
-        for pid_str in pids_str.lines() {
-            if let Ok(pid) = pid_str.trim().parse::<u32>() {
-                // 使用 lsof 获取进程的工作目录
-                let lsof_output = Command::new("lsof")
-                    .args(["-p", &pid.to_string()])
-                    .output()
-                    .ok();
-
-                if let Some(lsof) = lsof_output {
-                    let lsof_str = String::from_utf8_lossy(&lsof.stdout);
-                    // 查找 cwd 行
-                    for line in lsof_str.lines() {
-                        if line.contains("cwd") {
-                            // 提取 cwd 路径（lsof 输出的最后一列）
-                            let parts: Vec<&str> = line.split_whitespace().collect();
-                            if parts.len() >= 9 {
-                                let process_cwd = parts[8..].join(" ");
-                                // 精确匹配 CWD
-                                if process_cwd == cwd {
-                                    println!("找到精确匹配的 CLI 进程: {} (PID={}, CWD={})", cli_name, pid, process_cwd);
-                                    return Some(pid);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    // 精确匹配 CWD
+    if let Some(process) = process_monitor::get_cli_processes(registry)
+        .into_iter()
+        .find(|p| p.cwd.as_deref() == Some(cwd))
+    {
+        println!(
+            "找到精确匹配的 CLI 进程: {} (PID={}, CWD={})",
+            process.cli_id, process.pid, cwd
+        );
+        return Some(process.pid);
     }
 
     println!("未找到精确匹配 CWD 的 CLI 进程");
@@ -71,9 +45,11 @@ pub fn get_parent_app_for_pid(pid: u32) -> Option<String> {
 
         // 获取进程信息
         println!("第{}层: 查询 PID={}", i + 1, current_pid);
-        if let Some((cmd_path, ppid)) = get_process_info(current_pid) {
-            let cmd_lower = cmd_path.to_lowercase();
-            println!("检查进程: PID={}, CMD={}", current_pid, cmd_path);
+        if let Some(details) = inspector().details(current_pid) {
+            let cmd_lower = details.cmdline.to_lowercase();
+            let name_lower = details.name.to_lowercase();
+            let ppid = details.ppid;
+            println!("检查进程: PID={}, CMD={}", current_pid, details.cmdline);
 
             // 检查是否是已知的终端应用（通过路径匹配）
             for terminal in TERMINAL_APPS {
@@ -82,6 +58,7 @@ pub fn get_parent_app_for_pid(pid: u32) -> Option<String> {
                 if cmd_lower.contains(&format!("{}.app", terminal_lower))
                     || cmd_lower.contains(&format!("/{}", terminal_lower))
                     || cmd_lower == terminal_lower
+                    || name_lower == terminal_lower
                 {
                     println!("找到终端应用: {}", terminal);
                     return Some(terminal.to_string());
@@ -94,6 +71,7 @@ pub fn get_parent_app_for_pid(pid: u32) -> Option<String> {
                 if cmd_lower.contains(&format!("{}.app", ide_lower))
                     || cmd_lower.contains(&format!("/{}", ide_lower))
                     || cmd_lower == ide_lower
+                    || name_lower == ide_lower
                 {
                     println!("找到 IDE 应用: {}", ide);
                     return Some(ide.to_string());
@@ -116,39 +94,6 @@ pub fn get_parent_app_for_pid(pid: u32) -> Option<String> {
     None
 }
 
-/// 获取进程信息：(进程名/路径, 父进程 PID)
-fn get_process_info(pid: u32) -> Option<(String, u32)> {
-    // 分开获取 args 和 ppid，避免 ps 输出被截断
-    // 注意：ps -o args=,ppid= 会截断长路径，所以需要分开调用
-
-    // 获取完整命令路径（使用 -ww 确保不截断）
-    let args_output = Command::new("ps")
-        .args(["-ww", "-p", &pid.to_string(), "-o", "args="])
-        .output()
-        .ok()?;
-
-    let cmd = String::from_utf8_lossy(&args_output.stdout)
-        .trim()
-        .to_string();
-
-    if cmd.is_empty() {
-        return None;
-    }
-
-    // 获取父进程 PID
-    let ppid_output = Command::new("ps")
-        .args(["-p", &pid.to_string(), "-o", "ppid="])
-        .output()
-        .ok()?;
-
-    let ppid = String::from_utf8_lossy(&ppid_output.stdout)
-        .trim()
-        .parse::<u32>()
-        .ok()?;
-
-    Some((cmd, ppid))
-}
-
 /// 激活指定的应用
 fn activate_app(app_name: &str) -> Result<(), String> {
     let script = format!(
@@ -236,13 +181,25 @@ fn activate_app_window(app_name: &str, cwd: Option<&str>) -> Result<(), String>
 }
 
 /// 智能置顶：根据 CLI 的 PID 和工作目录激活正确的应用和窗口
-pub fn bring_cli_to_front(pid: Option<u32>, cwd: Option<&str>) -> Result<(), String> {
+pub fn bring_cli_to_front(
+    pid: Option<u32>,
+    cwd: Option<&str>,
+    registry: &CliRegistry,
+) -> Result<(), String> {
     println!("智能置顶: PID={:?}, CWD={:?}", pid, cwd);
 
-    // 策略1: 如果有 CWD，通过 CWD 查找真正的 CLI 进程
-    // （因为 hooks 传来的 PID 是脚本进程，已经退出）
+    // 策略1: 如果有 PID，通过进程树找到父应用
+    // （PID 已由 IPC 服务器校验为真正的 CLI 进程，同一目录下的多个会话不会混淆）
+    if let Some(p) = pid {
+        if let Some(app_name) = get_parent_app_for_pid(p) {
+            println!("通过 PID 进程树找到父应用: {}", app_name);
+            return activate_app_window(&app_name, cwd);
+        }
+    }
+
+    // 策略2: 如果有 CWD，通过 CWD 查找 CLI 进程（旧版 hook 客户端没有有效 PID 时兜底）
     if let Some(dir) = cwd {
-        if let Some(cli_pid) = find_cli_pid_by_cwd(dir) {
+        if let Some(cli_pid) = find_cli_pid_by_cwd(dir, registry) {
             if let Some(app_name) = get_parent_app_for_pid(cli_pid) {
                 println!("通过 CWD 找到父应用: {}", app_name);
                 return activate_app_window(&app_name, cwd);
@@ -250,14 +207,6 @@ pub fn bring_cli_to_front(pid: Option<u32>, cwd: Option<&str>) -> Result<(), Str
         }
     }
 
-    // 策略2: 如果有 PID，尝试通过进程树找到父应用（兜底）
-    if let Some(p) = pid {
-        if let Some(app_name) = get_parent_app_for_pid(p) {
-            println!("通过 PID 进程树找到父应用: {}", app_name);
-            return activate_app_window(&app_name, cwd);
-        }
-    }
-
     // 策略3: 使用传统的优先级方式
     println!("使用传统优先级方式激活");
     bring_terminal_to_front_legacy(cwd)
@@ -285,5 +234,5 @@ fn bring_terminal_to_front_legacy(cwd: Option<&str>) -> Result<(), String> {
 
 /// 将终端应用置于最前（兼容旧接口）
 pub fn bring_terminal_to_front() -> Result<(), String> {
-    bring_cli_to_front(None, None)
+    bring_terminal_to_front_legacy(None)
 }